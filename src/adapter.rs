@@ -27,8 +27,9 @@ use crate::pointer_ops::PointerOps;
 ///
 /// In most cases you do not need to implement this trait manually: the
 /// `intrusive_adapter!` macro will generate the necessary implementation for a
-/// given type and its link field. However it is possible to implement it
-/// manually if the intrusive link is not a direct field of the object type.
+/// given type and its link field, which may also be nested inside other
+/// fields of the object. However it is possible to implement it manually if the
+/// link is not stored inline in the object at all.
 ///
 /// It is also possible to create stateful adapters.
 /// This allows links and containers to be separated and avoids the need for objects to be modified to
@@ -140,6 +141,41 @@ macro_rules! container_of {
 /// supported in the generic argument list. You must list any trait bounds in
 /// a separate `where` clause at the end of the macro.
 ///
+/// # Nested fields
+///
+/// The link may be nested inside other fields of the object, in which case it
+/// is given as a path of field names, tuple indices and array indices:
+///
+/// ```rust,ignore
+/// intrusive_adapter!(Adapter = Pointer: Value { header.links[1].0: LinkType });
+/// ```
+///
+/// Chained tuple indices such as `outer.0.1` are supported as well. The lexer
+/// turns `0.1` into a single float literal, which is matched as one path
+/// segment and passed through unchanged, so it still indexes both tuples.
+///
+/// The path must lead to a link stored inline in the object. A path which goes
+/// through a `Deref` implementation (such as a `Box` field) or an overloaded
+/// index operator (such as a `Vec` field) is rejected at compile time.
+///
+/// Nested paths require Rust 1.65 or later, since the offset of the link is
+/// computed in a constant. A plain field name works on all supported compilers.
+///
+/// # Keys
+///
+/// An adapter for an `RBTree` can also implement `KeyAdapter` by adding a
+/// key clause after the link field. The key is extracted by a closure-like
+/// expression and must implement `Ord`. The key type may borrow from the
+/// object using the `'a` lifetime, which therefore can't be used as one of the
+/// generic arguments of the adapter:
+///
+/// ```rust,ignore
+/// intrusive_adapter!(Adapter = Pointer: Value { link_field: LinkType, key: u64 = |v| v.id });
+/// intrusive_adapter!(Adapter = Pointer: Value { link_field: LinkType, key: &'a str = |v| &v.name });
+/// ```
+///
+/// A custom comparator requires a manual implementation of `ComparatorAdapter`.
+///
 /// # Examples
 ///
 /// ```
@@ -160,12 +196,53 @@ macro_rules! container_of {
 ///     val: T,
 /// }
 /// intrusive_adapter!(MyAdapter3<'a, T> = &'a Test2<T>: Test2<T> { link: LinkedListLink } where T: ?Sized + Clone + 'a);
+///
+/// pub struct Header {
+///     links: [LinkedListLink; 2],
+/// }
+/// pub struct Test3 {
+///     header: Header,
+///     tree: (u32, RBTreeLink),
+/// }
+/// intrusive_adapter!(MyAdapter4 = Box<Test3>: Test3 { header.links[1]: LinkedListLink });
+/// intrusive_adapter!(MyAdapter5 = Box<Test3>: Test3 { tree.1: RBTreeLink });
+///
+/// pub struct Test4 {
+///     link: RBTreeLink,
+///     id: u64,
+///     name: String,
+/// }
+/// intrusive_adapter!(MyAdapter6 = Box<Test4>: Test4 { link: RBTreeLink, key: u64 = |v| v.id });
+/// intrusive_adapter!(MyAdapter7 = Box<Test4>: Test4 {
+///     link: RBTreeLink,
+///     key: (&'a str, u64) = |v| (&v.name, v.id),
+/// });
 /// ```
 #[macro_export]
 macro_rules! intrusive_adapter {
     (@impl
         $(#[$attr:meta])* ($($privacy:tt)*) $name:ident ($($args:tt),*)
-        = $pointer:ty: $value:path { $field:ident: $link:ty } $($where_:tt)*
+        = $pointer:ty: $value:path {
+            $($field:tt $([$index:literal])*).+ : $link:ty,
+            key: $key:ty = |$key_value:ident| $get_key:expr $(,)?
+        } $($where_:tt)*
+    ) => {
+        intrusive_adapter!(@impl
+            $(#[$attr])* ($($privacy)*) $name ($($args),*)
+            = $pointer: $value { $($field $([$index])*).+ : $link } $($where_)*
+        );
+        impl<'a, $($args),*> $crate::KeyAdapter<'a> for $name<$($args),*> $($where_)* {
+            type Key = $key;
+
+            #[inline]
+            fn get_key(&self, $key_value: &'a $value) -> $key {
+                $get_key
+            }
+        }
+    };
+    (@impl
+        $(#[$attr:meta])* ($($privacy:tt)*) $name:ident ($($args:tt),*)
+        = $pointer:ty: $value:path { $($field:tt $([$index:literal])*).+ : $link:ty } $($where_:tt)*
     ) => {
         #[allow(explicit_outlives_requirements)]
         $(#[$attr])*
@@ -188,8 +265,9 @@ macro_rules! intrusive_adapter {
                 Self::NEW
             }
         }
-        #[allow(dead_code)]
+        #[allow(dead_code, unsafe_code)]
         impl<$($args),*> $name<$($args),*> $($where_)* {
+            intrusive_adapter!(@link_offset $value, $link, $($field $([$index])*).+);
             pub const NEW: Self = $name {
                 link_ops: <$link as $crate::DefaultLinkOps>::NEW,
                 pointer_ops: $crate::DefaultPointerOps::<$pointer>::new(),
@@ -206,13 +284,13 @@ macro_rules! intrusive_adapter {
 
             #[inline]
             unsafe fn get_value(&self, link: <Self::LinkOps as $crate::LinkOps>::LinkPtr) -> *const <Self::PointerOps as $crate::PointerOps>::Value {
-                $crate::container_of!(link.as_ptr(), $value, $field)
+                (link.as_ptr() as *const u8).sub(Self::link_offset()) as *const $value
             }
             #[inline]
             unsafe fn get_link(&self, value: *const <Self::PointerOps as $crate::PointerOps>::Value) -> <Self::LinkOps as $crate::LinkOps>::LinkPtr {
                 // We need to do this instead of just accessing the field directly
                 // to strictly follow the stack borrow rules.
-                let ptr = (value as *const u8).add($crate::offset_of!($value, $field));
+                let ptr = (value as *const u8).add(Self::link_offset());
                 core::ptr::NonNull::new_unchecked(ptr as *mut _)
             }
             #[inline]
@@ -229,6 +307,35 @@ macro_rules! intrusive_adapter {
             }
         }
     };
+    (@link_offset $value:path, $link:ty, $field:ident) => {
+        #[inline]
+        fn link_offset() -> usize {
+            $crate::offset_of!($value, $field)
+        }
+    };
+    (@link_offset $value:path, $link:ty, $($field:tt $([$index:literal])*).+) => {
+        // Offset of the link within the value. This is computed in a constant
+        // so that a field path which goes through a `Deref` impl or an
+        // overloaded index operator, and therefore doesn't point into the
+        // value itself, is rejected at compile time.
+        const LINK_OFFSET: usize = {
+            // Also expand the path outside of an unsafe block, so that it
+            // can't smuggle in unsafe code.
+            let _ = |value: &$value| {
+                let _: &$link = &value.$($field $([$index])*).+;
+            };
+            let value = core::mem::MaybeUninit::<$value>::uninit();
+            let value = value.as_ptr();
+            let link: *const $link = unsafe {
+                core::ptr::addr_of!((*value).$($field $([$index])*).+)
+            };
+            unsafe { (link as *const u8).offset_from(value as *const u8) as usize }
+        };
+        #[inline]
+        fn link_offset() -> usize {
+            Self::LINK_OFFSET
+        }
+    };
     (@find_generic
         $(#[$attr:meta])* ($($privacy:tt)*) $name:ident ($($prev:tt)*) > $($rest:tt)*
     ) => {
@@ -271,8 +378,11 @@ macro_rules! intrusive_adapter {
 
 #[cfg(test)]
 mod tests {
-    use crate::LinkedListLink;
+    use crate::{LinkedListLink, RBTreeLink};
+    use std::boxed::Box;
     use std::rc::Rc;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
 
     struct Obj {
         link: LinkedListLink,
@@ -283,4 +393,112 @@ mod tests {
         /// Test doc comment
         ObjAdapter1 = Rc<Obj>: Obj { link: LinkedListLink }
     }
+
+    struct Header {
+        links: [LinkedListLink; 2],
+    }
+    struct Nested {
+        value: u32,
+        header: Header,
+        tuple: (u8, Header),
+        pairs: (u8, (LinkedListLink, LinkedListLink)),
+    }
+    intrusive_adapter!(NestedAdapter1 = Rc<Nested>: Nested { header.links[1]: LinkedListLink });
+    intrusive_adapter!(NestedAdapter2 = Rc<Nested>: Nested { tuple.1.links[0]: LinkedListLink });
+    intrusive_adapter!(NestedAdapter3 = Rc<Nested>: Nested { pairs.1.1: LinkedListLink });
+
+    #[test]
+    fn test_nested_field() {
+        use crate::{Adapter, LinkedList};
+
+        let obj = Rc::new(Nested {
+            value: 1,
+            header: Header {
+                links: [LinkedListLink::new(), LinkedListLink::new()],
+            },
+            tuple: (
+                0,
+                Header {
+                    links: [LinkedListLink::new(), LinkedListLink::new()],
+                },
+            ),
+            pairs: (0, (LinkedListLink::new(), LinkedListLink::new())),
+        });
+        let adapter = NestedAdapter2::new();
+        unsafe {
+            let link = adapter.get_link(&*obj);
+            assert_eq!(link.as_ptr() as *const _, &obj.tuple.1.links[0]);
+            assert_eq!(adapter.get_value(link), &*obj as *const _);
+        }
+
+        let mut a = LinkedList::new(NestedAdapter1::new());
+        let mut b = LinkedList::new(adapter);
+        let mut c = LinkedList::new(NestedAdapter3::new());
+        a.push_back(obj.clone());
+        b.push_back(obj.clone());
+        c.push_back(obj.clone());
+        assert!(obj.header.links[1].is_linked());
+        assert!(!obj.header.links[0].is_linked());
+        assert!(obj.tuple.1.links[0].is_linked());
+        assert!(obj.pairs.1 .1.is_linked());
+        assert!(!obj.pairs.1 .0.is_linked());
+        assert_eq!(a.front().get().unwrap().value, 1);
+        assert_eq!(b.front().get().unwrap().value, 1);
+        assert_eq!(c.front().get().unwrap().value, 1);
+    }
+
+    struct KeyObj<T> {
+        link: RBTreeLink,
+        id: u64,
+        name: String,
+        extra: T,
+    }
+    intrusive_adapter!(IdAdapter<T> = Box<KeyObj<T>>: KeyObj<T> { link: RBTreeLink, key: u64 = |v| v.id });
+    intrusive_adapter!(NameAdapter<T> = Rc<KeyObj<T>>: KeyObj<T> {
+        link: RBTreeLink,
+        key: &'a str = |v| &v.name,
+    } where T: Clone);
+    intrusive_adapter!(TupleAdapter = Box<KeyObj<()>>: KeyObj<()> {
+        link: RBTreeLink,
+        key: (&'a str, u64) = |v| (&v.name, v.id)
+    });
+
+    fn make_key_obj<T>(id: u64, name: &str, extra: T) -> KeyObj<T> {
+        KeyObj {
+            link: RBTreeLink::new(),
+            id,
+            name: name.to_string(),
+            extra,
+        }
+    }
+
+    #[test]
+    fn test_key_clause() {
+        use crate::RBTree;
+
+        let mut a = RBTree::new(IdAdapter::new());
+        for &(id, name) in &[(3, "c"), (1, "a"), (2, "b")] {
+            a.insert(Box::new(make_key_obj(id, name, 0u8)));
+        }
+        let ids: Vec<_> = a.iter().map(|x| x.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        assert_eq!(a.find(&2).get().unwrap().name, "b");
+
+        let mut b = RBTree::new(NameAdapter::new());
+        for &(id, name) in &[(1, "b"), (2, "c"), (3, "a")] {
+            b.insert(Rc::new(make_key_obj(id, name, 0u8)));
+        }
+        let ids: Vec<_> = b.iter().map(|x| x.id).collect();
+        assert_eq!(ids, [3, 1, 2]);
+        assert_eq!(b.find("c").get().unwrap().id, 2);
+        assert_eq!(b.front().get().unwrap().extra, 0);
+
+        let mut c = RBTree::new(TupleAdapter::new());
+        for &(id, name) in &[(2, "a"), (1, "b"), (1, "a")] {
+            c.insert(Box::new(make_key_obj(id, name, ())));
+        }
+        let keys: Vec<_> = c.iter().map(|x| (x.name.as_str(), x.id)).collect();
+        assert_eq!(keys, [("a", 1), ("a", 2), ("b", 1)]);
+        assert_eq!(c.find(&("a", 2)).get().unwrap().id, 2);
+    }
 }
@@ -0,0 +1,293 @@
+// Copyright 2016 Amanieu d'Antras
+// Copyright 2020 Amari Robinson
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+/// Trait for a lookup value which can be compared with keys of type `K`.
+///
+/// Collections ordered by `NaturalOrder` use this instead of `Borrow` for
+/// lookups, which allows a key to be looked up using a value that the key
+/// cannot be borrowed as. It is implemented automatically for every `Q: Ord`
+/// which the key can be borrowed as.
+///
+/// Since the lookup type is chosen by the caller, this trait is implemented by
+/// the lookup type, which also means that it can be implemented for a local
+/// lookup type even if the key type is defined in another crate. As a result
+/// the lookup type can no longer be inferred from the key type, so lookups
+/// which do not involve a value, such as `Unbounded` bounds, need to name it
+/// explicitly.
+///
+/// The ordering must be consistent with the `Ord` implementation of the key.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::intrusive_adapter;
+/// use intrusive_collections::{Bound, Comparable, KeyAdapter, RBTree, RBTreeLink};
+/// use std::cmp::Ordering;
+///
+/// struct S {
+///     link: RBTreeLink,
+///     name: String,
+///     version: u32,
+/// }
+///
+/// intrusive_adapter!(MyAdapter = Box<S>: S { link: RBTreeLink });
+/// impl<'a> KeyAdapter<'a> for MyAdapter {
+///     type Key = (&'a str, u32);
+///     fn get_key(&self, s: &'a S) -> (&'a str, u32) { (&s.name, s.version) }
+/// }
+///
+/// // Lookup value which is ordered before all versions of a name.
+/// struct Name<'a>(&'a str);
+/// impl<'a, 'b> Comparable<(&'a str, u32)> for Name<'b> {
+///     fn compare(&self, key: &(&'a str, u32)) -> Ordering {
+///         self.0.cmp(key.0).then(Ordering::Less)
+///     }
+/// }
+///
+/// let mut tree = RBTree::new(MyAdapter::new());
+/// for &(name, version) in &[("a", 1), ("b", 1), ("b", 2), ("c", 1)] {
+///     let name = name.to_string();
+///     tree.insert(Box::new(S { link: RBTreeLink::new(), name, version }));
+/// }
+/// let first = tree.lower_bound(Bound::Included(&Name("b")));
+/// assert_eq!(first.get().map(|s| (&s.name[..], s.version)), Some(("b", 1)));
+/// ```
+pub trait Comparable<K: ?Sized> {
+    /// Compares `self` with the key `key`.
+    fn compare(&self, key: &K) -> Ordering;
+}
+
+impl<Q: ?Sized + Ord, K: ?Sized + Borrow<Q>> Comparable<K> for Q {
+    #[inline]
+    fn compare(&self, key: &K) -> Ordering {
+        self.cmp(key.borrow())
+    }
+}
+
+/// Trait for a comparator which defines the order of keys in an intrusive
+/// collection.
+///
+/// Comparators may be stateful: they are stored in the adapter and obtained
+/// through `ComparatorAdapter::comparator`.
+///
+/// The ordering must be a total order which is consistent between calls.
+/// Failure to do this may lead to `find`, `upper_bound`, `lower_bound` and
+/// `range` returning incorrect results, but will not cause any memory
+/// unsafety.
+pub trait Compare<K: ?Sized> {
+    /// Compares the key `a` with the key `b`.
+    fn compare(&self, a: &K, b: &K) -> Ordering;
+}
+
+/// Trait for a comparator which supports looking up keys of type `K` using a
+/// value of type `Q`.
+///
+/// The ordering must be consistent with the `Compare<K>` implementation of
+/// the comparator.
+pub trait CompareWith<Q: ?Sized, K: ?Sized> {
+    /// Compares the lookup value `q` with the key `key`.
+    fn compare_with(&self, q: &Q, key: &K) -> Ordering;
+}
+
+/// Comparator which orders keys according to their `Ord` implementation.
+///
+/// Lookups are supported for any type which implements `Comparable` for the
+/// key type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NaturalOrder;
+
+impl<K: ?Sized + Ord> Compare<K> for NaturalOrder {
+    #[inline]
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl<Q: ?Sized + Comparable<K>, K: ?Sized> CompareWith<Q, K> for NaturalOrder {
+    #[inline]
+    fn compare_with(&self, q: &Q, key: &K) -> Ordering {
+        q.compare(key)
+    }
+}
+
+/// Comparator which reverses the order of another comparator.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::intrusive_adapter;
+/// use intrusive_collections::{ComparatorAdapter, NaturalOrder, RBTree, RBTreeLink, ReverseOrder};
+///
+/// struct S {
+///     link: RBTreeLink,
+///     key: u32,
+/// }
+///
+/// intrusive_adapter!(MyAdapter = Box<S>: S { link: RBTreeLink });
+/// impl<'a> ComparatorAdapter<'a> for MyAdapter {
+///     type SortKey = u32;
+///     type Comparator = ReverseOrder<NaturalOrder>;
+///     fn get_sort_key(&self, s: &'a S) -> u32 { s.key }
+///     fn comparator(&self) -> &ReverseOrder<NaturalOrder> { &ReverseOrder(NaturalOrder) }
+/// }
+///
+/// let mut tree = RBTree::new(MyAdapter::new());
+/// for key in 0..3 {
+///     tree.insert(Box::new(S { link: RBTreeLink::new(), key }));
+/// }
+/// assert_eq!(tree.iter().map(|s| s.key).collect::<Vec<_>>(), [2, 1, 0]);
+/// assert_eq!(tree.find(&1).get().unwrap().key, 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ReverseOrder<C>(pub C);
+
+impl<K: ?Sized, C: Compare<K>> Compare<K> for ReverseOrder<C> {
+    #[inline]
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self.0.compare(a, b).reverse()
+    }
+}
+
+impl<Q: ?Sized, K: ?Sized, C: CompareWith<Q, K>> CompareWith<Q, K> for ReverseOrder<C> {
+    #[inline]
+    fn compare_with(&self, q: &Q, key: &K) -> Ordering {
+        self.0.compare_with(q, key).reverse()
+    }
+}
+
+/// Comparator which orders keys by a field extracted from them, using another
+/// comparator for the extracted fields.
+///
+/// Lookups are done using the field rather than the whole key, and support
+/// any lookup type which the comparator for the field supports.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::intrusive_adapter;
+/// use intrusive_collections::{ByField, ComparatorAdapter, NaturalOrder, RBTree, RBTreeLink};
+///
+/// struct S {
+///     link: RBTreeLink,
+///     id: u32,
+///     rank: u32,
+/// }
+///
+/// type ByRank = ByField<fn(&(u32, u32)) -> &u32>;
+/// const BY_RANK: ByRank = ByField::new(|key| &key.1, NaturalOrder);
+///
+/// // Orders elements by rank and ignores the id.
+/// intrusive_adapter!(MyAdapter = Box<S>: S { link: RBTreeLink });
+/// impl<'a> ComparatorAdapter<'a> for MyAdapter {
+///     type SortKey = (u32, u32);
+///     type Comparator = ByRank;
+///     fn get_sort_key(&self, s: &'a S) -> (u32, u32) { (s.id, s.rank) }
+///     fn comparator(&self) -> &ByRank { &BY_RANK }
+/// }
+///
+/// let mut tree = RBTree::new(MyAdapter::new());
+/// tree.insert(Box::new(S { link: RBTreeLink::new(), id: 1, rank: 2 }));
+/// tree.insert(Box::new(S { link: RBTreeLink::new(), id: 2, rank: 1 }));
+/// assert_eq!(tree.iter().map(|s| s.id).collect::<Vec<_>>(), [2, 1]);
+/// assert_eq!(tree.find(&2).get().unwrap().id, 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ByField<F, C = NaturalOrder> {
+    /// Function which extracts the field from a key.
+    pub extract: F,
+    /// Comparator used for the extracted fields.
+    pub compare: C,
+}
+
+impl<F, C> ByField<F, C> {
+    /// Creates a new `ByField` comparator.
+    #[inline]
+    pub const fn new(extract: F, compare: C) -> ByField<F, C> {
+        ByField { extract, compare }
+    }
+}
+
+impl<K: ?Sized, T: ?Sized, F, C> Compare<K> for ByField<F, C>
+where
+    F: Fn(&K) -> &T,
+    C: Compare<T>,
+{
+    #[inline]
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self.compare.compare((self.extract)(a), (self.extract)(b))
+    }
+}
+
+impl<Q: ?Sized, K: ?Sized, T: ?Sized, F, C> CompareWith<Q, K> for ByField<F, C>
+where
+    F: Fn(&K) -> &T,
+    C: CompareWith<Q, T>,
+{
+    #[inline]
+    fn compare_with(&self, q: &Q, key: &K) -> Ordering {
+        self.compare.compare_with(q, (self.extract)(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByField, Comparable, Compare, CompareWith, NaturalOrder, ReverseOrder};
+    use core::cmp::Ordering;
+    use std::string::String;
+
+    #[test]
+    fn test_comparators() {
+        assert_eq!(NaturalOrder.compare(&1, &2), Ordering::Less);
+        assert_eq!(
+            NaturalOrder.compare_with("b", &String::from("a")),
+            Ordering::Greater
+        );
+        assert_eq!(
+            ReverseOrder(NaturalOrder).compare(&1, &2),
+            Ordering::Greater
+        );
+        assert_eq!(
+            ReverseOrder(ReverseOrder(NaturalOrder)).compare_with(&1, &2),
+            Ordering::Less
+        );
+
+        fn second(x: &(i32, i32)) -> &i32 {
+            &x.1
+        }
+        let by_second = ByField::new(second, NaturalOrder);
+        assert_eq!(by_second.compare(&(1, 2), &(2, 1)), Ordering::Greater);
+        assert_eq!(by_second.compare(&(1, 2), &(2, 2)), Ordering::Equal);
+        assert_eq!(by_second.compare_with(&2, &(3, 1)), Ordering::Greater);
+        let by_second_rev = ByField::new(second, ReverseOrder(NaturalOrder));
+        assert_eq!(by_second_rev.compare(&(1, 2), &(2, 1)), Ordering::Less);
+        assert_eq!(by_second_rev.compare_with(&2, &(3, 1)), Ordering::Less);
+    }
+
+    #[test]
+    fn test_comparable() {
+        struct Prefix(u8);
+        impl Comparable<(u8, u8)> for Prefix {
+            fn compare(&self, key: &(u8, u8)) -> Ordering {
+                self.0.cmp(&key.0).then(Ordering::Less)
+            }
+        }
+        assert_eq!(3.compare(&3), Ordering::Equal);
+        assert_eq!("b".compare(&String::from("a")), Ordering::Greater);
+        assert_eq!(
+            NaturalOrder.compare_with(&Prefix(1), &(1, 0)),
+            Ordering::Less
+        );
+        assert_eq!(
+            NaturalOrder.compare_with(&Prefix(2), &(1, 9)),
+            Ordering::Greater
+        );
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright 2020 Amari Robinson
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use core::fmt;
+use core::mem;
+use core::ptr::NonNull;
+
+use crate::adapter::Adapter;
+use crate::link_ops::{DefaultLinkOps, LinkOps};
+use crate::pointer_ops::{DefaultPointerOps, PointerOps};
+
+/// Adapter which locates the link at an offset only known at runtime.
+///
+/// This is useful for objects whose layout is not known at compile time, for
+/// example structures allocated by foreign code which come in several
+/// versions with the link at different positions. In all other cases, prefer
+/// the `intrusive_adapter!` macro which computes the offset at compile time.
+///
+/// `P` is the pointer type used to own the objects and `L` is the link type,
+/// for example `LinkedListLink`.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::{offset_of, DynOffsetAdapter, LinkedList, LinkedListLink, UnsafeRef};
+///
+/// #[repr(C)]
+/// struct Header {
+///     version: u32,
+/// }
+///
+/// #[repr(C)]
+/// struct ObjectV2 {
+///     header: Header,
+///     flags: u32,
+///     link: LinkedListLink,
+/// }
+///
+/// let offset = offset_of!(ObjectV2, link);
+/// let adapter = unsafe { DynOffsetAdapter::<UnsafeRef<Header>, LinkedListLink>::new(offset) };
+/// let mut list = LinkedList::new(adapter.unwrap());
+///
+/// let obj = Box::new(ObjectV2 {
+///     header: Header { version: 2 },
+///     flags: 0,
+///     link: LinkedListLink::new(),
+/// });
+/// let header = unsafe { UnsafeRef::from_raw(Box::into_raw(obj) as *const Header) };
+/// list.push_back(header);
+/// assert_eq!(list.front().get().unwrap().version, 2);
+///
+/// let header = list.pop_front().unwrap();
+/// unsafe { drop(Box::from_raw(UnsafeRef::into_raw(header) as *mut ObjectV2)) };
+/// ```
+pub struct DynOffsetAdapter<P, L: DefaultLinkOps> {
+    link_ops: L::Ops,
+    pointer_ops: DefaultPointerOps<P>,
+    offset: usize,
+}
+
+unsafe impl<P, L: DefaultLinkOps> Send for DynOffsetAdapter<P, L> {}
+unsafe impl<P, L: DefaultLinkOps> Sync for DynOffsetAdapter<P, L> {}
+
+impl<P, L: DefaultLinkOps> DynOffsetAdapter<P, L> {
+    /// Creates a new `DynOffsetAdapter` for links located `offset` bytes from
+    /// the start of each object.
+    ///
+    /// Returns `None` if `offset` is not suitably aligned for a link of type
+    /// `L`.
+    ///
+    /// # Safety
+    ///
+    /// Every object inserted into a collection using this adapter must contain
+    /// a link of type `L` at `offset` bytes from its start, which must remain
+    /// valid for as long as the object is linked.
+    #[inline]
+    pub unsafe fn new(offset: usize) -> Option<Self> {
+        if offset & (mem::align_of::<L>() - 1) != 0 {
+            return None;
+        }
+        Some(DynOffsetAdapter {
+            link_ops: L::NEW,
+            pointer_ops: DefaultPointerOps::new(),
+            offset,
+        })
+    }
+
+    /// Returns the offset of the link within each object.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<P, L: DefaultLinkOps> Clone for DynOffsetAdapter<P, L>
+where
+    L::Ops: Clone,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        DynOffsetAdapter {
+            link_ops: self.link_ops.clone(),
+            pointer_ops: self.pointer_ops,
+            offset: self.offset,
+        }
+    }
+}
+
+impl<P, L: DefaultLinkOps> Copy for DynOffsetAdapter<P, L> where L::Ops: Copy {}
+
+impl<P, L: DefaultLinkOps> fmt::Debug for DynOffsetAdapter<P, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynOffsetAdapter")
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+unsafe impl<P, L> Adapter for DynOffsetAdapter<P, L>
+where
+    L: DefaultLinkOps,
+    L::Ops: LinkOps<LinkPtr = NonNull<L>>,
+    DefaultPointerOps<P>: PointerOps,
+    <DefaultPointerOps<P> as PointerOps>::Value: Sized,
+{
+    type LinkOps = L::Ops;
+    type PointerOps = DefaultPointerOps<P>;
+
+    #[inline]
+    unsafe fn get_value(
+        &self,
+        link: <Self::LinkOps as LinkOps>::LinkPtr,
+    ) -> *const <Self::PointerOps as PointerOps>::Value {
+        (link.as_ptr() as *const u8).sub(self.offset) as *const _
+    }
+
+    #[inline]
+    unsafe fn get_link(
+        &self,
+        value: *const <Self::PointerOps as PointerOps>::Value,
+    ) -> <Self::LinkOps as LinkOps>::LinkPtr {
+        let ptr = (value as *const u8).add(self.offset);
+        NonNull::new_unchecked(ptr as *mut _)
+    }
+
+    #[inline]
+    fn link_ops(&self) -> &Self::LinkOps {
+        &self.link_ops
+    }
+
+    #[inline]
+    fn link_ops_mut(&mut self) -> &mut Self::LinkOps {
+        &mut self.link_ops
+    }
+
+    #[inline]
+    fn pointer_ops(&self) -> &Self::PointerOps {
+        &self.pointer_ops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynOffsetAdapter;
+    use crate::offset_of;
+    use crate::{
+        LinkedList, LinkedListLink, RBTree, RBTreeLink, SinglyLinkedList, SinglyLinkedListLink,
+        XorLinkedList, XorLinkedListLink,
+    };
+    use std::boxed::Box;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    #[derive(Default)]
+    struct Obj {
+        value: u32,
+        link1: LinkedListLink,
+        link2: SinglyLinkedListLink,
+        link3: XorLinkedListLink,
+        link4: RBTreeLink,
+    }
+
+    fn make_obj(value: u32) -> Obj {
+        Obj {
+            value,
+            ..Obj::default()
+        }
+    }
+
+    #[test]
+    fn test_dyn_offset() {
+        let offset = offset_of!(Obj, link1);
+        assert!(unsafe { DynOffsetAdapter::<Box<Obj>, LinkedListLink>::new(offset + 1) }.is_none());
+
+        let adapter = unsafe { DynOffsetAdapter::<Box<Obj>, LinkedListLink>::new(offset) };
+        let mut a = LinkedList::new(adapter.unwrap());
+        a.push_back(Box::new(make_obj(1)));
+        a.push_front(Box::new(make_obj(2)));
+        assert_eq!(a.iter().map(|x| x.value).collect::<Vec<_>>(), [2, 1]);
+
+        let adapter = unsafe {
+            DynOffsetAdapter::<Rc<Obj>, SinglyLinkedListLink>::new(offset_of!(Obj, link2))
+        };
+        let mut b = SinglyLinkedList::new(adapter.unwrap());
+        let adapter =
+            unsafe { DynOffsetAdapter::<Rc<Obj>, XorLinkedListLink>::new(offset_of!(Obj, link3)) };
+        let mut c = XorLinkedList::new(adapter.unwrap());
+        let obj = Rc::new(make_obj(3));
+        b.push_front(obj.clone());
+        c.push_back(obj.clone());
+        assert!(obj.link2.is_linked());
+        assert!(obj.link3.is_linked());
+        assert_eq!(b.front().get().unwrap().value, 3);
+        assert_eq!(c.back().get().unwrap().value, 3);
+
+        let adapter =
+            unsafe { DynOffsetAdapter::<Box<Obj>, RBTreeLink>::new(offset_of!(Obj, link4)) };
+        let mut d = RBTree::new(adapter.unwrap());
+        d.front_mut().insert_after(Box::new(make_obj(4)));
+        assert_eq!(d.front().get().unwrap().value, 4);
+        assert_eq!(d.front_mut().remove().unwrap().value, 4);
+        assert!(d.is_empty());
+    }
+}
@@ -0,0 +1,462 @@
+// Copyright 2020 Amari Robinson
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Reference counted pointer which keeps its counts inside the object.
+
+use crate::alloc::alloc::{dealloc, Layout};
+use crate::alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{self, AtomicUsize, Ordering};
+
+// Refuse to increment a count past this value, so that it can't overflow even
+// if many threads race past the check.
+const MAX_REFCOUNT: usize = isize::MAX as usize;
+
+// =============================================================================
+// RefCount
+// =============================================================================
+
+/// Reference counts embedded in an object managed by an `IntrusiveArc`.
+///
+/// A new `RefCount` accounts for the `IntrusiveArc` returned by
+/// `IntrusiveArc::new`.
+pub struct RefCount {
+    strong: AtomicUsize,
+    // Number of `IntrusiveWeak`, plus one for all strong references.
+    weak: AtomicUsize,
+}
+
+impl RefCount {
+    /// Creates a new `RefCount`.
+    #[inline]
+    pub const fn new() -> RefCount {
+        RefCount {
+            strong: AtomicUsize::new(1),
+            weak: AtomicUsize::new(1),
+        }
+    }
+
+    #[inline]
+    fn increment(count: &AtomicUsize) {
+        if count.fetch_add(1, Ordering::Relaxed) > MAX_REFCOUNT {
+            count.fetch_sub(1, Ordering::Relaxed);
+            panic!("IntrusiveArc reference count overflow");
+        }
+    }
+
+    // Returns true if this released the last reference.
+    #[inline]
+    fn decrement(count: &AtomicUsize) -> bool {
+        if count.fetch_sub(1, Ordering::Release) != 1 {
+            return false;
+        }
+        atomic::fence(Ordering::Acquire);
+        true
+    }
+}
+
+// Provide an implementation of Clone which simply creates new counts. This
+// allows structs containing a `RefCount` to derive Clone.
+impl Clone for RefCount {
+    #[inline]
+    fn clone(&self) -> RefCount {
+        RefCount::new()
+    }
+}
+
+impl Default for RefCount {
+    #[inline]
+    fn default() -> RefCount {
+        RefCount::new()
+    }
+}
+
+impl fmt::Debug for RefCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefCount")
+            .field("strong", &self.strong.load(Ordering::Relaxed))
+            .field("weak", &(self.weak.load(Ordering::Relaxed) - 1))
+            .finish()
+    }
+}
+
+/// Trait for objects with an embedded `RefCount`, which can be managed by an
+/// `IntrusiveArc`.
+///
+/// # Safety
+///
+/// `ref_count` must always return the same `RefCount`, which must be stored
+/// inline in `self`.
+pub unsafe trait RefCounted {
+    /// Returns the reference counts of `self`.
+    fn ref_count(&self) -> &RefCount;
+}
+
+// =============================================================================
+// IntrusiveArc
+// =============================================================================
+
+/// Thread-safe reference counted pointer which stores its counts in the object
+/// itself, through an embedded `RefCount`.
+///
+/// Unlike `Arc`, this doesn't need a separate header in front of the object,
+/// and a pointer to the object is enough to create a new reference to it.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::{intrusive_adapter, IntrusiveArc, LinkedList, LinkedListLink};
+/// use intrusive_collections::{RefCount, RefCounted};
+///
+/// struct Request {
+///     link: LinkedListLink,
+///     refs: RefCount,
+///     id: u32,
+/// }
+///
+/// unsafe impl RefCounted for Request {
+///     fn ref_count(&self) -> &RefCount {
+///         &self.refs
+///     }
+/// }
+///
+/// intrusive_adapter!(RequestAdapter = IntrusiveArc<Request>: Request { link: LinkedListLink });
+///
+/// let mut pending = LinkedList::new(RequestAdapter::new());
+/// pending.push_back(IntrusiveArc::new(Request {
+///     link: LinkedListLink::new(),
+///     refs: RefCount::new(),
+///     id: 1,
+/// }));
+///
+/// let request = pending.front().clone_pointer().unwrap();
+/// assert_eq!(IntrusiveArc::strong_count(&request), 2);
+/// assert_eq!(request.id, 1);
+/// ```
+pub struct IntrusiveArc<T: RefCounted> {
+    ptr: NonNull<T>,
+    _marker: PhantomData<T>,
+}
+
+unsafe impl<T: RefCounted + Send + Sync> Send for IntrusiveArc<T> {}
+unsafe impl<T: RefCounted + Send + Sync> Sync for IntrusiveArc<T> {}
+
+impl<T: RefCounted> IntrusiveArc<T> {
+    /// Moves `value` into a new allocation and returns the only strong
+    /// reference to it.
+    #[inline]
+    pub fn new(value: T) -> IntrusiveArc<T> {
+        let count = value.ref_count();
+        debug_assert_eq!(count.strong.load(Ordering::Relaxed), 1);
+        debug_assert_eq!(count.weak.load(Ordering::Relaxed), 1);
+        IntrusiveArc {
+            ptr: unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(value))) },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates an `IntrusiveArc` from a raw pointer, without changing the
+    /// reference count.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `IntrusiveArc::into_raw`, and each
+    /// such pointer must only be converted back once.
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const T) -> IntrusiveArc<T> {
+        IntrusiveArc {
+            ptr: NonNull::new_unchecked(ptr as *mut T),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Consumes the `IntrusiveArc` and returns a raw pointer to the object,
+    /// without changing the reference count.
+    #[inline]
+    pub fn into_raw(this: Self) -> *const T {
+        let ptr = this.ptr.as_ptr();
+        core::mem::forget(this);
+        ptr
+    }
+
+    /// Creates a new strong reference from a reference to an object which is
+    /// managed by an `IntrusiveArc`.
+    ///
+    /// # Safety
+    ///
+    /// `value` must be managed by an `IntrusiveArc`, for example because it
+    /// was obtained from a collection of `IntrusiveArc`.
+    #[inline]
+    pub unsafe fn from_ref(value: &T) -> IntrusiveArc<T> {
+        RefCount::increment(&value.ref_count().strong);
+        IntrusiveArc::from_raw(value)
+    }
+
+    /// Creates a new weak reference to the object.
+    #[inline]
+    pub fn downgrade(this: &Self) -> IntrusiveWeak<T> {
+        let count = this.ref_count();
+        RefCount::increment(&count.weak);
+        IntrusiveWeak {
+            ptr: this.ptr,
+            count: NonNull::from(count),
+        }
+    }
+
+    /// Returns the number of strong references to the object.
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        this.ref_count().strong.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of weak references to the object.
+    #[inline]
+    pub fn weak_count(this: &Self) -> usize {
+        this.ref_count().weak.load(Ordering::SeqCst) - 1
+    }
+
+    /// Returns `true` if both pointers point to the same object.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+}
+
+impl<T: RefCounted> Clone for IntrusiveArc<T> {
+    #[inline]
+    fn clone(&self) -> IntrusiveArc<T> {
+        RefCount::increment(&self.ref_count().strong);
+        IntrusiveArc {
+            ptr: self.ptr,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: RefCounted> Drop for IntrusiveArc<T> {
+    #[inline]
+    fn drop(&mut self) {
+        let count = NonNull::from(self.ref_count());
+        unsafe {
+            if RefCount::decrement(&count.as_ref().strong) {
+                ptr::drop_in_place(self.ptr.as_ptr());
+
+                // Release the weak reference collectively held by the strong
+                // references, which frees the memory if there are no others.
+                drop(IntrusiveWeak {
+                    ptr: self.ptr,
+                    count,
+                });
+            }
+        }
+    }
+}
+
+impl<T: RefCounted> Deref for IntrusiveArc<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: RefCounted> AsRef<T> for IntrusiveArc<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: RefCounted> Borrow<T> for IntrusiveArc<T> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: RefCounted + fmt::Debug> fmt::Debug for IntrusiveArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+// =============================================================================
+// IntrusiveWeak
+// =============================================================================
+
+/// Weak reference to an object managed by an `IntrusiveArc`.
+///
+/// The object is dropped when the last `IntrusiveArc` is dropped, but its
+/// memory is only freed once all weak references are gone.
+pub struct IntrusiveWeak<T: RefCounted> {
+    ptr: NonNull<T>,
+    // The counts can't be reached through `ptr` once the object is dropped.
+    count: NonNull<RefCount>,
+}
+
+unsafe impl<T: RefCounted + Send + Sync> Send for IntrusiveWeak<T> {}
+unsafe impl<T: RefCounted + Send + Sync> Sync for IntrusiveWeak<T> {}
+
+impl<T: RefCounted> IntrusiveWeak<T> {
+    /// Attempts to create a strong reference to the object, returning `None`
+    /// if it has already been dropped.
+    #[inline]
+    pub fn upgrade(&self) -> Option<IntrusiveArc<T>> {
+        let strong = unsafe { &self.count.as_ref().strong };
+        let mut n = strong.load(Ordering::Relaxed);
+        loop {
+            if n == 0 {
+                return None;
+            }
+            assert!(n <= MAX_REFCOUNT, "IntrusiveArc reference count overflow");
+            match strong.compare_exchange_weak(n, n + 1, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => {
+                    return Some(IntrusiveArc {
+                        ptr: self.ptr,
+                        _marker: PhantomData,
+                    })
+                }
+                Err(x) => n = x,
+            }
+        }
+    }
+
+    /// Returns the number of strong references to the object.
+    #[inline]
+    pub fn strong_count(&self) -> usize {
+        unsafe { self.count.as_ref().strong.load(Ordering::SeqCst) }
+    }
+}
+
+impl<T: RefCounted> Clone for IntrusiveWeak<T> {
+    #[inline]
+    fn clone(&self) -> IntrusiveWeak<T> {
+        RefCount::increment(unsafe { &self.count.as_ref().weak });
+        IntrusiveWeak {
+            ptr: self.ptr,
+            count: self.count,
+        }
+    }
+}
+
+impl<T: RefCounted> Drop for IntrusiveWeak<T> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            if RefCount::decrement(&self.count.as_ref().weak) {
+                dealloc(self.ptr.as_ptr() as *mut u8, Layout::new::<T>());
+            }
+        }
+    }
+}
+
+impl<T: RefCounted> fmt::Debug for IntrusiveWeak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("(IntrusiveWeak)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntrusiveArc, RefCount, RefCounted};
+    use crate::{LinkedList, LinkedListLink};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct Obj {
+        link: LinkedListLink,
+        refs: RefCount,
+        value: u32,
+        dropped: Rc<Cell<bool>>,
+    }
+
+    unsafe impl RefCounted for Obj {
+        fn ref_count(&self) -> &RefCount {
+            &self.refs
+        }
+    }
+
+    impl Drop for Obj {
+        fn drop(&mut self) {
+            self.dropped.set(true);
+        }
+    }
+
+    intrusive_adapter!(ObjAdapter = IntrusiveArc<Obj>: Obj { link: LinkedListLink });
+
+    fn make_obj(value: u32) -> (IntrusiveArc<Obj>, Rc<Cell<bool>>) {
+        let dropped = Rc::new(Cell::new(false));
+        let obj = IntrusiveArc::new(Obj {
+            link: LinkedListLink::new(),
+            refs: RefCount::new(),
+            value,
+            dropped: dropped.clone(),
+        });
+        (obj, dropped)
+    }
+
+    #[test]
+    fn test_counts() {
+        let (a, dropped) = make_obj(1);
+        let b = a.clone();
+        assert_eq!(IntrusiveArc::strong_count(&a), 2);
+        assert!(IntrusiveArc::ptr_eq(&a, &b));
+
+        let raw = IntrusiveArc::into_raw(b);
+        let c = unsafe { IntrusiveArc::from_ref(&*raw) };
+        assert_eq!(IntrusiveArc::strong_count(&a), 3);
+        drop(unsafe { IntrusiveArc::from_raw(raw) });
+        drop(c);
+        assert_eq!(IntrusiveArc::strong_count(&a), 1);
+        assert!(!dropped.get());
+        drop(a);
+        assert!(dropped.get());
+    }
+
+    #[test]
+    fn test_weak() {
+        let (a, dropped) = make_obj(1);
+        let w = IntrusiveArc::downgrade(&a);
+        let w2 = w.clone();
+        assert_eq!(IntrusiveArc::weak_count(&a), 2);
+        assert_eq!(w.upgrade().unwrap().value, 1);
+        assert_eq!(w.strong_count(), 1);
+
+        drop(a);
+        assert!(dropped.get());
+        assert_eq!(w.strong_count(), 0);
+        assert!(w.upgrade().is_none());
+        drop(w);
+        assert!(w2.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_collection() {
+        let (a, dropped) = make_obj(1);
+        let mut l = LinkedList::new(ObjAdapter::new());
+        l.push_back(a.clone());
+        assert_eq!(IntrusiveArc::strong_count(&a), 2);
+
+        let b = l.front().clone_pointer().unwrap();
+        assert_eq!(b.value, 1);
+        assert_eq!(IntrusiveArc::strong_count(&a), 3);
+        drop(b);
+
+        let w = IntrusiveArc::downgrade(&a);
+        drop(a);
+        assert!(!dropped.get());
+        assert!(w.upgrade().unwrap().link.is_linked());
+        l.clear();
+        assert!(dropped.get());
+        assert!(w.upgrade().is_none());
+    }
+}
@@ -7,6 +7,7 @@
 // copied, modified, or distributed except according to those terms.
 
 use crate::adapter::Adapter;
+use crate::compare::{Compare, NaturalOrder};
 use crate::pointer_ops::PointerOps;
 
 /// Extension of the `Adapter` trait to provide a way of extracting a key from
@@ -64,3 +65,92 @@ pub trait KeyAdapter<'a>: Adapter {
     /// Gets the key for the given object.
     fn get_key(&self, value: &'a <Self::PointerOps as PointerOps>::Value) -> Self::Key;
 }
+
+/// Extension of the `Adapter` trait to provide a way of extracting a key from
+/// an object, along with a comparator which defines the order of those keys.
+/// `RBTree` uses this to keep its elements in order.
+///
+/// This trait is implemented automatically for every `KeyAdapter` whose key
+/// type implements `Ord`, using `NaturalOrder` as the comparator. Implement
+/// it directly instead of `KeyAdapter` to order keys in some other way. Since
+/// the comparator is obtained from the adapter, it may be stateful.
+///
+/// Lookups using a type other than the key type require the comparator to
+/// implement `CompareWith` for that type.
+///
+/// The associated items are named differently from those of `KeyAdapter` so
+/// that calls such as `adapter.get_key(value)` remain unambiguous when both
+/// traits are in scope.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::intrusive_adapter;
+/// use intrusive_collections::{Compare, CompareWith, ComparatorAdapter, RBTree, RBTreeLink};
+/// use std::cmp::Ordering;
+///
+/// struct S {
+///     link: RBTreeLink,
+///     name: String,
+/// }
+///
+/// // Comparator which orders strings while ignoring ASCII case.
+/// struct IgnoreCase;
+/// impl<'a> Compare<&'a str> for IgnoreCase {
+///     fn compare(&self, a: &&'a str, b: &&'a str) -> Ordering {
+///         self.compare_with(*a, b)
+///     }
+/// }
+/// impl<'a> CompareWith<str, &'a str> for IgnoreCase {
+///     fn compare_with(&self, q: &str, key: &&'a str) -> Ordering {
+///         let q = q.bytes().map(|c| c.to_ascii_lowercase());
+///         q.cmp(key.bytes().map(|c| c.to_ascii_lowercase()))
+///     }
+/// }
+///
+/// intrusive_adapter!(MyAdapter = Box<S>: S { link : RBTreeLink });
+/// impl<'a> ComparatorAdapter<'a> for MyAdapter {
+///     type SortKey = &'a str;
+///     type Comparator = IgnoreCase;
+///     fn get_sort_key(&self, s: &'a S) -> &'a str { &s.name }
+///     fn comparator(&self) -> &IgnoreCase { &IgnoreCase }
+/// }
+///
+/// let mut tree = RBTree::new(MyAdapter::new());
+/// for name in &["b", "C", "a"] {
+///     tree.insert(Box::new(S { link: RBTreeLink::new(), name: name.to_string() }));
+/// }
+/// assert_eq!(tree.iter().map(|s| &s.name[..]).collect::<Vec<_>>(), ["a", "b", "C"]);
+/// assert_eq!(tree.find("c").get().unwrap().name, "C");
+/// ```
+pub trait ComparatorAdapter<'a>: Adapter {
+    /// Type of the key returned by `get_sort_key`.
+    type SortKey;
+
+    /// Type of the comparator used to order keys.
+    type Comparator: Compare<Self::SortKey>;
+
+    /// Gets the key by which the given object is ordered.
+    fn get_sort_key(&self, value: &'a <Self::PointerOps as PointerOps>::Value) -> Self::SortKey;
+
+    /// Returns a reference to the comparator used to order keys.
+    fn comparator(&self) -> &Self::Comparator;
+}
+
+impl<'a, A: KeyAdapter<'a>> ComparatorAdapter<'a> for A
+where
+    A::Key: Ord,
+{
+    type SortKey = A::Key;
+    type Comparator = NaturalOrder;
+
+    #[inline]
+    fn get_sort_key(&self, value: &'a <Self::PointerOps as PointerOps>::Value) -> A::Key {
+        self.get_key(value)
+    }
+
+    #[inline]
+    fn comparator(&self) -> &NaturalOrder {
+        &NaturalOrder
+    }
+}
@@ -86,7 +86,9 @@
 //!
 //! For red-black trees, the adapter must also implement the `KeyAdapter` trait
 //! which allows a key to be extracted from an object. This key is then used to
-//! keep all elements in the tree in ascending order.
+//! keep all elements in the tree in ascending order. To order keys using a
+//! custom comparator instead of their `Ord` implementation, implement the
+//! `ComparatorAdapter` trait instead.
 //!
 //! ```
 //! use intrusive_collections::intrusive_adapter;
@@ -267,7 +269,7 @@
 #![warn(missing_docs)]
 #![warn(rust_2018_idioms)]
 #![no_std]
-#![cfg_attr(feature = "nightly", feature(const_fn))]
+#![cfg_attr(feature = "nightly", feature(const_fn, allocator_api))]
 #![allow(clippy::declare_interior_mutable_const, clippy::collapsible_if)]
 
 #[cfg(feature = "alloc")]
@@ -279,27 +281,45 @@ extern crate std;
 mod unsafe_ref;
 #[macro_use]
 mod adapter;
+mod compare;
+mod dyn_offset_adapter;
+#[cfg(feature = "alloc")]
+mod intrusive_arc;
 mod key_adapter;
 mod link_ops;
 mod pointer_ops;
+mod tagged_link;
 mod unchecked_option;
+mod unsized_link;
 
 pub mod linked_list;
+pub mod multi_index;
 pub mod rbtree;
 pub mod singly_linked_list;
 pub mod xor_linked_list;
 
 pub use crate::adapter::Adapter;
-pub use crate::key_adapter::KeyAdapter;
-pub use crate::link_ops::{DefaultLinkOps, LinkOps};
+pub use crate::compare::{ByField, Comparable, Compare, CompareWith, NaturalOrder, ReverseOrder};
+pub use crate::dyn_offset_adapter::DynOffsetAdapter;
+#[cfg(feature = "alloc")]
+pub use crate::intrusive_arc::{IntrusiveArc, IntrusiveWeak, RefCount, RefCounted};
+pub use crate::key_adapter::{ComparatorAdapter, KeyAdapter};
+pub use crate::link_ops::{DefaultLinkOps, LinkOps, OwnerLinkOps};
 pub use crate::linked_list::Link as LinkedListLink;
 pub use crate::linked_list::LinkedList;
-pub use crate::pointer_ops::{DefaultPointerOps, PointerOps};
+pub use crate::multi_index::{IndexRef, MultiIndex, Position};
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+pub use crate::pointer_ops::AllocPointerOps;
+pub use crate::pointer_ops::{
+    DataMut, DefaultPointerOps, PinnedPointerOps, PointerOps, UniquePointerOps,
+};
 pub use crate::rbtree::Link as RBTreeLink;
 pub use crate::rbtree::RBTree;
 pub use crate::singly_linked_list::Link as SinglyLinkedListLink;
 pub use crate::singly_linked_list::SinglyLinkedList;
+pub use crate::tagged_link::{TaggedLink, TaggedLinkOps};
 pub use crate::unsafe_ref::UnsafeRef;
+pub use crate::unsized_link::{HasUnsizedLink, UnsizedAdapter, UnsizedLink};
 pub use crate::xor_linked_list::Link as XorLinkedListLink;
 pub use crate::xor_linked_list::XorLinkedList;
 pub use memoffset::offset_of;
@@ -314,3 +334,37 @@ pub enum Bound<T> {
     /// An infinite endpoint. Indicates that there is no bound in this direction.
     Unbounded,
 }
+
+impl<T> Bound<T> {
+    /// Converts from `&Bound<T>` to `Bound<&T>`.
+    #[inline]
+    pub fn as_ref(&self) -> Bound<&T> {
+        match *self {
+            Bound::Included(ref x) => Bound::Included(x),
+            Bound::Excluded(ref x) => Bound::Excluded(x),
+            Bound::Unbounded => Bound::Unbounded,
+        }
+    }
+}
+
+impl<T> From<core::ops::Bound<T>> for Bound<T> {
+    #[inline]
+    fn from(bound: core::ops::Bound<T>) -> Bound<T> {
+        match bound {
+            core::ops::Bound::Included(x) => Bound::Included(x),
+            core::ops::Bound::Excluded(x) => Bound::Excluded(x),
+            core::ops::Bound::Unbounded => Bound::Unbounded,
+        }
+    }
+}
+
+impl<T> From<Bound<T>> for core::ops::Bound<T> {
+    #[inline]
+    fn from(bound: Bound<T>) -> core::ops::Bound<T> {
+        match bound {
+            Bound::Included(x) => core::ops::Bound::Included(x),
+            Bound::Excluded(x) => core::ops::Bound::Excluded(x),
+            Bound::Unbounded => core::ops::Bound::Unbounded,
+        }
+    }
+}
@@ -26,6 +26,52 @@ pub unsafe trait LinkOps {
     /// # Safety
     /// An implementation of `release_link` must not panic.
     unsafe fn release_link(&mut self, ptr: Self::LinkPtr);
+
+    /// Whether these link operations record an owner in each link, in which
+    /// case collections call `adopt_link` for links moved between them.
+    ///
+    /// This defaults to `false`, which lets collections skip those calls.
+    const TRACKS_OWNER: bool = false;
+
+    /// Records that a link which is already acquired now belongs to this
+    /// instance, after it was moved in from another collection without being
+    /// released.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// # Safety
+    /// `ptr` must point to a link which is currently acquired.
+    #[inline]
+    unsafe fn adopt_link(&mut self, _ptr: Self::LinkPtr) {}
+
+    /// Gives this instance a new identity, so that none of the links it
+    /// previously acquired or adopted are considered owned by it any more.
+    ///
+    /// Collections call this when they hand their links over to a copy of
+    /// their adapter, or forget them without releasing them.
+    ///
+    /// The default implementation does nothing.
+    #[inline]
+    fn renew_owner(&mut self) {}
+}
+
+/// Link operations which can tell whether a link belongs to them.
+///
+/// This allows collections to safely look up an element from a reference to
+/// it, since an element linked into a different collection is detected.
+///
+/// # Safety
+/// `is_owned` must only return `true` for a link which this instance acquired
+/// or adopted, and which has not since been released, adopted by another
+/// instance, or forgotten through `renew_owner`. Collections call
+/// `renew_owner` on one side whenever they copy their adapter, so copies made
+/// before any link was acquired must not share an identity either.
+pub unsafe trait OwnerLinkOps: LinkOps {
+    /// Returns `true` if the link currently belongs to this instance.
+    ///
+    /// # Safety
+    /// `ptr` must point to a valid link, which may or may not be acquired.
+    unsafe fn is_owned(&self, ptr: Self::LinkPtr) -> bool;
 }
 
 /// The default implementation of `LinkOps` associated with a link type.
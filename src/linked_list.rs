@@ -10,13 +10,16 @@
 
 use core::cell::Cell;
 use core::fmt;
-use core::ptr::NonNull;
-
-use crate::link_ops::{self, DefaultLinkOps};
-use crate::pointer_ops::PointerOps;
-use crate::singly_linked_list::SinglyLinkedListOps;
+use core::mem::{self, ManuallyDrop};
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+
+use crate::link_ops::{self, DefaultLinkOps, OwnerLinkOps};
+use crate::multi_index::Position;
+use crate::pointer_ops::{DataMut, PinnedPointerOps, PointerOps, UniquePointerOps};
+use crate::singly_linked_list::{SinglyLinkedList, SinglyLinkedListOps};
 use crate::unchecked_option::UncheckedOptionExt;
-use crate::xor_linked_list::XorLinkedListOps;
+use crate::xor_linked_list::{XorLinkedList, XorLinkedListOps};
 use crate::Adapter;
 
 // =============================================================================
@@ -105,6 +108,19 @@ impl DefaultLinkOps for Link {
 // An object containing a link can be sent to another thread if it is unlinked.
 unsafe impl Send for Link {}
 
+// Freeing an object which is still linked leaves a dangling pointer in the
+// collection, so turn this into a panic when debugging.
+#[cfg(feature = "debug-unsafe-ref")]
+impl Drop for Link {
+    #[inline]
+    fn drop(&mut self) {
+        assert!(
+            !self.is_linked(),
+            "`LinkedListLink` dropped while still linked into a `LinkedList`"
+        );
+    }
+}
+
 // Provide an implementation of Clone which simply initializes the new link as
 // unlinked. This allows structs containing a link to derive Clone.
 impl Clone for Link {
@@ -342,6 +358,22 @@ unsafe fn splice<T: LinkedListOps>(
     }
 }
 
+// Records that the links from `start` to `end` now belong to `link_ops`, after
+// they were moved in from another list.
+#[inline]
+unsafe fn adopt<T: LinkedListOps>(link_ops: &mut T, start: T::LinkPtr, end: T::LinkPtr) {
+    if T::TRACKS_OWNER {
+        let mut current = start;
+        loop {
+            link_ops.adopt_link(current);
+            if current == end {
+                break;
+            }
+            current = link_ops.next(current).unwrap_unchecked();
+        }
+    }
+}
+
 // =============================================================================
 // Cursor, CursorMut
 // =============================================================================
@@ -456,6 +488,29 @@ where
         prev.move_prev();
         prev
     }
+
+    /// Converts the cursor into a `RevCursor` pointing to the same element,
+    /// which moves through the `LinkedList` in the opposite direction.
+    #[inline]
+    pub fn rev(self) -> RevCursor<'a, A> {
+        RevCursor { cursor: self }
+    }
+}
+
+// Allow a cursor to pick the element to remove or modify in a `MultiIndex`.
+impl<'a, A: Adapter> From<Cursor<'a, A>> for Position<'a, <A::PointerOps as PointerOps>::Value>
+where
+    A::LinkOps: LinkedListOps,
+{
+    #[inline]
+    fn from(cursor: Cursor<'a, A>) -> Self {
+        unsafe {
+            Position::new(
+                cursor.list as *const LinkedList<A> as *const (),
+                cursor.get(),
+            )
+        }
+    }
 }
 
 /// A cursor which provides mutable access to a `LinkedList`.
@@ -586,6 +641,22 @@ where
         }
     }
 
+    /// Removes the current element from the `LinkedList` and passes it to
+    /// `dispose`.
+    ///
+    /// This behaves like `remove`, except that the removed element is handed
+    /// to `dispose` instead of being returned, and the cursor is moved to point
+    /// to the next element.
+    ///
+    /// Returns `true` if an element was removed.
+    #[inline]
+    pub fn remove_and_dispose<F>(&mut self, dispose: F) -> bool
+    where
+        F: FnOnce(<A::PointerOps as PointerOps>::Pointer),
+    {
+        self.remove().map(dispose).is_some()
+    }
+
     /// Removes the current element from the `LinkedList` and inserts another
     /// object in its place.
     ///
@@ -693,6 +764,7 @@ where
                 let tail = list.tail.unwrap_unchecked();
 
                 let link_ops = self.list.adapter.link_ops_mut();
+                adopt(link_ops, head, tail);
 
                 if let Some(current) = self.current {
                     splice(link_ops, head, tail, Some(current), link_ops.next(current));
@@ -721,6 +793,7 @@ where
                 let tail = list.tail.unwrap_unchecked();
 
                 let link_ops = self.list.adapter.link_ops_mut();
+                adopt(link_ops, head, tail);
 
                 if let Some(current) = self.current {
                     splice(link_ops, head, tail, link_ops.prev(current), Some(current));
@@ -748,6 +821,8 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         if let Some(current) = self.current {
             unsafe {
                 let mut list = LinkedList {
@@ -761,6 +836,10 @@ where
                     list.tail = None;
                 }
                 self.list.adapter.link_ops_mut().set_next(current, None);
+                list.adapter.link_ops_mut().renew_owner();
+                if let (Some(head), Some(tail)) = (list.head, list.tail) {
+                    adopt(list.adapter.link_ops_mut(), head, tail);
+                }
                 self.list.tail = self.current;
                 list
             }
@@ -772,6 +851,7 @@ where
             };
             self.list.head = None;
             self.list.tail = None;
+            self.list.adapter.link_ops_mut().renew_owner();
             list
         }
     }
@@ -787,6 +867,8 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         if let Some(current) = self.current {
             unsafe {
                 let mut list = LinkedList {
@@ -800,6 +882,10 @@ where
                     list.head = None;
                 }
                 self.list.adapter.link_ops_mut().set_prev(current, None);
+                list.adapter.link_ops_mut().renew_owner();
+                if let (Some(head), Some(tail)) = (list.head, list.tail) {
+                    adopt(list.adapter.link_ops_mut(), head, tail);
+                }
                 self.list.head = self.current;
                 list
             }
@@ -811,11 +897,156 @@ where
             };
             self.list.head = None;
             self.list.tail = None;
+            self.list.adapter.link_ops_mut().renew_owner();
             list
         }
     }
 }
 
+impl<'a, A: Adapter> Cursor<'a, A>
+where
+    A::LinkOps: LinkedListOps,
+    A::PointerOps: PinnedPointerOps,
+{
+    /// Returns a pinned reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This is only available for collections of pinned pointers such as
+    /// `Pin<Box<T>>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_pin(&self) -> Option<Pin<&'a <A::PointerOps as PointerOps>::Value>> {
+        self.get().map(|value| unsafe { Pin::new_unchecked(value) })
+    }
+}
+
+impl<'a, A: Adapter> CursorMut<'a, A>
+where
+    A::LinkOps: LinkedListOps,
+    A::PointerOps: PinnedPointerOps,
+{
+    /// Returns a pinned reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This is only available for collections of pinned pointers such as
+    /// `Pin<Box<T>>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_pin(&self) -> Option<Pin<&<A::PointerOps as PointerOps>::Value>> {
+        self.get().map(|value| unsafe { Pin::new_unchecked(value) })
+    }
+}
+
+impl<'a, A: Adapter> CursorMut<'a, A>
+where
+    A::LinkOps: LinkedListOps,
+    A::PointerOps: UniquePointerOps,
+    <A::PointerOps as PointerOps>::Value: DataMut,
+{
+    /// Returns a mutable reference to the data of the object that the cursor
+    /// is currently pointing to.
+    ///
+    /// This is only available for collections of uniquely owned pointers such
+    /// as `Box<T>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_data_mut(
+        &mut self,
+    ) -> Option<&mut <<A::PointerOps as PointerOps>::Value as DataMut>::Data> {
+        let value = unsafe { self.list.adapter.get_value(self.current?) };
+        Some(unsafe { (*(value as *mut <A::PointerOps as PointerOps>::Value)).data_mut() })
+    }
+}
+
+/// A cursor which provides read-only access to a `LinkedList` in reverse
+/// order.
+///
+/// This wraps a `Cursor` and swaps the meaning of "next" and "previous", so
+/// that code written in terms of `move_next` walks the list from back to
+/// front. It is created by `Cursor::rev`.
+pub struct RevCursor<'a, A: Adapter>
+where
+    A::LinkOps: LinkedListOps,
+{
+    cursor: Cursor<'a, A>,
+}
+
+impl<'a, A: Adapter> Clone for RevCursor<'a, A>
+where
+    A::LinkOps: LinkedListOps,
+{
+    #[inline]
+    fn clone(&self) -> RevCursor<'a, A> {
+        RevCursor {
+            cursor: self.cursor.clone(),
+        }
+    }
+}
+
+impl<'a, A: Adapter> RevCursor<'a, A>
+where
+    A::LinkOps: LinkedListOps,
+{
+    /// Checks if the cursor is currently pointing to the null object.
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        self.cursor.is_null()
+    }
+
+    /// Returns a reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This returns `None` if the cursor is currently pointing to the null
+    /// object.
+    #[inline]
+    pub fn get(&self) -> Option<&'a <A::PointerOps as PointerOps>::Value> {
+        self.cursor.get()
+    }
+
+    /// Moves the cursor to the next element in reverse order, which is the
+    /// previous element of the `LinkedList`.
+    ///
+    /// If the cursor is pointer to the null object then this will move it to
+    /// the last element of the `LinkedList`.
+    #[inline]
+    pub fn move_next(&mut self) {
+        self.cursor.move_prev();
+    }
+
+    /// Moves the cursor to the previous element in reverse order, which is
+    /// the next element of the `LinkedList`.
+    ///
+    /// If the cursor is pointer to the null object then this will move it to
+    /// the first element of the `LinkedList`.
+    #[inline]
+    pub fn move_prev(&mut self) {
+        self.cursor.move_next();
+    }
+
+    /// Returns a cursor pointing to the next element in reverse order.
+    #[inline]
+    pub fn peek_next(&self) -> RevCursor<'_, A> {
+        let mut next = self.clone();
+        next.move_next();
+        next
+    }
+
+    /// Returns a cursor pointing to the previous element in reverse order.
+    #[inline]
+    pub fn peek_prev(&self) -> RevCursor<'_, A> {
+        let mut prev = self.clone();
+        prev.move_prev();
+        prev
+    }
+
+    /// Converts the cursor back into a `Cursor` which moves in the normal
+    /// direction.
+    #[inline]
+    pub fn rev(self) -> Cursor<'a, A> {
+        self.cursor
+    }
+}
+
 // =============================================================================
 // LinkedList
 // =============================================================================
@@ -859,6 +1090,36 @@ where
         }
     }
 
+    // Creates a `LinkedList` from the first and last elements of a list that
+    // was threaded through the links by another collection.
+    #[inline]
+    pub(crate) unsafe fn from_raw_parts(
+        head: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        tail: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        adapter: A,
+    ) -> LinkedList<A> {
+        LinkedList {
+            head,
+            tail,
+            adapter,
+        }
+    }
+
+    // Takes the `LinkedList` apart without unlinking its elements, so that
+    // they can be re-threaded into another collection sharing the same links.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn into_raw_parts(
+        self,
+    ) -> (
+        Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        A,
+    ) {
+        let list = ManuallyDrop::new(self);
+        (list.head, list.tail, unsafe { ptr::read(&list.adapter) })
+    }
+
     /// Creates an empty `LinkedList`.
     #[cfg(not(feature = "nightly"))]
     #[inline]
@@ -990,18 +1251,38 @@ where
     /// converted back to an owned pointer and then dropped.
     #[inline]
     pub fn clear(&mut self) {
+        self.clear_and_dispose(drop);
+    }
+
+    /// Removes all elements from the `LinkedList`, passing each one to
+    /// `dispose`.
+    ///
+    /// This behaves like `clear`, except that each element is converted back
+    /// to an owned pointer and handed to `dispose` instead of being dropped.
+    /// This can be used to return elements to a pool or to run some custom
+    /// teardown on them.
+    #[inline]
+    pub fn clear_and_dispose<F>(&mut self, mut dispose: F)
+    where
+        F: FnMut(<A::PointerOps as PointerOps>::Pointer),
+    {
         use link_ops::LinkOps;
 
         let mut current = self.head;
         self.head = None;
         self.tail = None;
+        // Renew the owner first, so that elements which are left linked if
+        // `dispose` panics don't appear to belong to this collection.
+        self.adapter.link_ops_mut().renew_owner();
         while let Some(x) = current {
             unsafe {
                 let next = self.adapter.link_ops().next(x);
                 self.adapter.link_ops_mut().release_link(x);
-                self.adapter
-                    .pointer_ops()
-                    .from_raw(self.adapter.get_value(x));
+                dispose(
+                    self.adapter
+                        .pointer_ops()
+                        .from_raw(self.adapter.get_value(x)),
+                );
                 current = next;
             }
         }
@@ -1015,8 +1296,11 @@ where
     /// `force_unlink` function on them.
     #[inline]
     pub fn fast_clear(&mut self) {
+        use link_ops::LinkOps;
+
         self.head = None;
         self.tail = None;
+        self.adapter.link_ops_mut().renew_owner();
     }
 
     /// Takes all the elements out of the `LinkedList`, leaving it empty.
@@ -1026,6 +1310,8 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         let list = LinkedList {
             head: self.head,
             tail: self.tail,
@@ -1033,6 +1319,7 @@ where
         };
         self.head = None;
         self.tail = None;
+        self.adapter.link_ops_mut().renew_owner();
         list
     }
 
@@ -1056,6 +1343,19 @@ where
         self.front_mut().remove()
     }
 
+    /// Removes the first element of the `LinkedList` and passes it to
+    /// `dispose`.
+    ///
+    /// Returns `true` if an element was removed, or `false` if the `LinkedList`
+    /// was empty.
+    #[inline]
+    pub fn pop_front_and_dispose<F>(&mut self, dispose: F) -> bool
+    where
+        F: FnOnce(<A::PointerOps as PointerOps>::Pointer),
+    {
+        self.pop_front().map(dispose).is_some()
+    }
+
     /// Removes the last element of the `LinkedList`.
     ///
     /// This returns `None` if the `LinkedList` is empty.
@@ -1063,6 +1363,114 @@ where
     pub fn pop_back(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
         self.back_mut().remove()
     }
+
+    /// Reverses the order of the elements in the `LinkedList`.
+    ///
+    /// This swaps the next and previous pointers of every element, and takes
+    /// O(n) time without allocating.
+    #[inline]
+    pub fn reverse(&mut self) {
+        let link_ops = self.adapter.link_ops_mut();
+        let mut current = self.head;
+        while let Some(x) = current {
+            unsafe {
+                let next = link_ops.next(x);
+                let prev = link_ops.prev(x);
+                link_ops.set_next(x, prev);
+                link_ops.set_prev(x, next);
+                current = next;
+            }
+        }
+        mem::swap(&mut self.head, &mut self.tail);
+    }
+}
+
+impl<A: Adapter> LinkedList<A>
+where
+    A::LinkOps: LinkedListOps + OwnerLinkOps,
+{
+    /// Returns a `CursorMut` pointing to the given element, or `None` if the
+    /// element is not part of this list.
+    ///
+    /// This is a safe version of `cursor_mut_from_ptr`, made possible by a
+    /// link type which records its owner, such as `TaggedLink`.
+    #[inline]
+    pub fn cursor_mut_for(
+        &mut self,
+        value: &<A::PointerOps as PointerOps>::Value,
+    ) -> Option<CursorMut<'_, A>> {
+        unsafe {
+            let link = self.adapter.get_link(value);
+            if self.adapter.link_ops().is_owned(link) {
+                Some(CursorMut {
+                    current: Some(link),
+                    list: self,
+                })
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Removes the given element from the list in O(1) time, or returns
+    /// `None` if the element is not part of this list.
+    #[inline]
+    pub fn remove_value(
+        &mut self,
+        value: &<A::PointerOps as PointerOps>::Value,
+    ) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        self.cursor_mut_for(value)?.remove()
+    }
+}
+
+impl<A: Adapter> LinkedList<A>
+where
+    A::LinkOps: LinkedListOps + XorLinkedListOps,
+{
+    /// Converts the `LinkedList` into a `XorLinkedList` containing the same
+    /// elements in the same order.
+    ///
+    /// The elements are re-threaded through their existing links in O(n) time
+    /// without being unlinked or converted back into owned pointers, which
+    /// requires the link type to support both collections.
+    #[inline]
+    pub fn into_xor_list(self) -> XorLinkedList<A> {
+        let (head, tail, mut adapter) = self.into_raw_parts();
+        unsafe {
+            let mut prev = None;
+            let mut current = head;
+            while let Some(x) = current {
+                current = LinkedListOps::next(adapter.link_ops(), x);
+                XorLinkedListOps::set(adapter.link_ops_mut(), x, prev, current);
+                prev = Some(x);
+            }
+            XorLinkedList::from_raw_parts(head, tail, adapter)
+        }
+    }
+}
+
+impl<A: Adapter> LinkedList<A>
+where
+    A::LinkOps: LinkedListOps + SinglyLinkedListOps,
+{
+    /// Converts the `LinkedList` into a `SinglyLinkedList` containing the same
+    /// elements in the same order.
+    ///
+    /// The elements are re-threaded through their existing links in O(n) time
+    /// without being unlinked or converted back into owned pointers, which
+    /// requires the link type to support both collections.
+    #[inline]
+    pub fn into_singly_list(self) -> SinglyLinkedList<A> {
+        let (head, _, mut adapter) = self.into_raw_parts();
+        unsafe {
+            let mut current = head;
+            while let Some(x) = current {
+                current = LinkedListOps::next(adapter.link_ops(), x);
+                SinglyLinkedListOps::set_next(adapter.link_ops_mut(), x, current);
+            }
+            SinglyLinkedList::from_raw_parts(head, adapter)
+        }
+    }
 }
 
 // Allow read-only access to values from multiple threads
@@ -1240,6 +1648,9 @@ where
 #[cfg(test)]
 mod tests {
     use super::{Link, LinkedList};
+    use crate::{SinglyLinkedList, XorLinkedList};
+    use core::cell::Cell;
+    use core::ptr;
     use std::fmt;
     use std::format;
     use std::rc::Rc;
@@ -1562,6 +1973,100 @@ mod tests {
         assert!(!d.link1.is_linked());
     }
 
+    #[test]
+    fn test_conversions() {
+        let objs = (0..5).map(make_obj).collect::<Vec<_>>();
+        let mut l = LinkedList::new(ObjAdapter1::new());
+        for x in &objs {
+            l.push_back(x.clone());
+        }
+
+        let x: XorLinkedList<ObjAdapter1> = l.into_xor_list();
+        assert_eq!(
+            x.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(
+            x.iter().rev().map(|x| x.value).collect::<Vec<_>>(),
+            [4, 3, 2, 1, 0]
+        );
+
+        let s: SinglyLinkedList<ObjAdapter1> = x.into_singly_list();
+        assert_eq!(
+            s.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+
+        let mut x: XorLinkedList<ObjAdapter1> = s.into_xor_list();
+        assert_eq!(x.pop_back().unwrap().value, 4);
+
+        let mut l: LinkedList<ObjAdapter1> = x.into_linked_list();
+        assert_eq!(
+            l.iter().rev().map(|x| x.value).collect::<Vec<_>>(),
+            [3, 2, 1, 0]
+        );
+        l.push_back(objs[4].clone());
+
+        let s: SinglyLinkedList<ObjAdapter1> = l.into_singly_list();
+        let l: LinkedList<ObjAdapter1> = s.into_linked_list();
+        assert_eq!(l.back().get().unwrap().value, 4);
+        assert_eq!(
+            l.iter().rev().map(|x| x.value).collect::<Vec<_>>(),
+            [4, 3, 2, 1, 0]
+        );
+        assert!(objs.iter().all(|x| x.link1.is_linked()));
+        drop(l);
+        assert!(objs.iter().all(|x| !x.link1.is_linked()));
+        assert!(objs.iter().all(|x| Rc::strong_count(x) == 1));
+
+        let empty = LinkedList::new(ObjAdapter1::new()).into_xor_list();
+        assert!(empty.into_singly_list().into_linked_list().is_empty());
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut l = LinkedList::new(ObjAdapter1::new());
+        l.reverse();
+        assert!(l.is_empty());
+        for v in 0..5 {
+            l.push_back(make_obj(v));
+        }
+        l.reverse();
+        assert_eq!(
+            l.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [4, 3, 2, 1, 0]
+        );
+        assert_eq!(
+            l.iter().rev().map(|x| x.value).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(l.pop_back().unwrap().value, 0);
+        l.push_front(make_obj(5));
+        l.reverse();
+        assert_eq!(
+            l.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5]
+        );
+
+        let mut c = l.cursor().rev();
+        assert!(c.is_null());
+        c.move_next();
+        assert_eq!(c.get().unwrap().value, 5);
+        assert_eq!(c.peek_next().get().unwrap().value, 4);
+        assert!(c.peek_prev().is_null());
+        c.move_next();
+        c.move_next();
+        assert_eq!(c.get().unwrap().value, 3);
+        c.move_prev();
+        assert_eq!(c.get().unwrap().value, 4);
+        let mut c = c.rev();
+        c.move_next();
+        assert_eq!(c.get().unwrap().value, 5);
+        let mut c = l.front().rev();
+        c.move_next();
+        assert!(c.is_null());
+    }
+
     #[test]
     fn test_multi_list() {
         let mut l1 = LinkedList::new(ObjAdapter1::new());
@@ -1667,4 +2172,184 @@ mod tests {
     fn test_clone_pointer_arc() {
         test_clone_pointer!(Arc, std::sync::Arc);
     }
+
+    #[test]
+    fn test_pinned() {
+        use core::marker::PhantomPinned;
+        use core::pin::Pin;
+        use std::boxed::Box;
+
+        // Element which refers to itself, and therefore must not move.
+        struct SelfRef {
+            link: Link,
+            value: u32,
+            this: Cell<*const SelfRef>,
+            _pin: PhantomPinned,
+        }
+        impl SelfRef {
+            fn init(self: Pin<&Self>) {
+                self.this.set(&*self);
+            }
+            fn check(self: Pin<&Self>) -> u32 {
+                assert_eq!(self.this.get(), &*self as *const _);
+                self.value
+            }
+        }
+        intrusive_adapter!(PinAdapter = Pin<Box<SelfRef>>: SelfRef { link: Link });
+
+        let mut l = LinkedList::new(PinAdapter::new());
+        for value in 1..4 {
+            let obj = Box::pin(SelfRef {
+                link: Link::new(),
+                value,
+                this: Cell::new(ptr::null()),
+                _pin: PhantomPinned,
+            });
+            obj.as_ref().init();
+            l.push_front(obj);
+        }
+        assert_eq!(l.front().get_pin().unwrap().check(), 3);
+        assert_eq!(l.back_mut().get_pin().unwrap().check(), 1);
+        assert!(l.cursor().get_pin().is_none());
+        let obj = l.pop_back().unwrap();
+        assert_eq!(obj.as_ref().check(), 1);
+        assert!(!obj.link.is_linked());
+    }
+
+    #[test]
+    fn test_data_mut() {
+        use crate::DataMut;
+        use std::boxed::Box;
+
+        struct Counter {
+            link: Link,
+            data: (u32, Vec<u32>),
+        }
+        unsafe impl DataMut for Counter {
+            type Data = (u32, Vec<u32>);
+            fn data_mut(&mut self) -> &mut (u32, Vec<u32>) {
+                &mut self.data
+            }
+        }
+        intrusive_adapter!(CounterAdapter = Box<Counter>: Counter { link: Link });
+
+        let mut l = LinkedList::new(CounterAdapter::new());
+        for i in 0..3 {
+            l.push_back(Box::new(Counter {
+                link: Link::new(),
+                data: (i, Vec::new()),
+            }));
+        }
+        let mut cur = l.front_mut();
+        while let Some(data) = cur.get_data_mut() {
+            data.0 *= 10;
+            data.1.push(data.0);
+            cur.move_next();
+        }
+        assert!(cur.get_data_mut().is_none());
+        let values: Vec<_> = l.iter().map(|x| (x.data.0, &x.data.1[..])).collect();
+        assert_eq!(values, [(0, &[0][..]), (10, &[10]), (20, &[20])]);
+    }
+
+    #[test]
+    fn test_dispose() {
+        let a = make_obj(1);
+        let b = make_obj(2);
+        let c = make_obj(3);
+        let mut l = LinkedList::new(ObjAdapter1::new());
+        l.push_back(a.clone());
+        l.push_back(b.clone());
+        l.push_back(c.clone());
+        l.push_back(make_obj(4));
+
+        let mut disposed = Vec::new();
+        assert!(l.pop_front_and_dispose(|x| disposed.push(x.value)));
+        let mut cur = l.front_mut();
+        assert!(cur.remove_and_dispose(|x| disposed.push(x.value)));
+        assert_eq!(cur.get().unwrap().value, 3);
+        assert_eq!(disposed, [1, 2]);
+        assert!(!a.link1.is_linked());
+        assert!(!b.link1.is_linked());
+
+        l.clear_and_dispose(|x| disposed.push(x.value));
+        assert!(l.is_empty());
+        assert_eq!(disposed, [1, 2, 3, 4]);
+        assert!(!c.link1.is_linked());
+        assert_eq!(Rc::strong_count(&c), 1);
+        assert!(!l.pop_front_and_dispose(|_| unreachable!()));
+        assert!(!l.cursor_mut().remove_and_dispose(|_| unreachable!()));
+    }
+
+    #[test]
+    fn test_remove_value() {
+        use crate::TaggedLink;
+        use std::panic::{self, AssertUnwindSafe};
+
+        struct Tagged {
+            link: TaggedLink<Link>,
+            value: u32,
+        }
+        intrusive_adapter!(TaggedAdapter = Rc<Tagged>: Tagged { link: TaggedLink<Link> });
+        let make = |value| {
+            Rc::new(Tagged {
+                link: TaggedLink::new(Link::new()),
+                value,
+            })
+        };
+        let values = |l: &LinkedList<TaggedAdapter>| l.iter().map(|x| x.value).collect::<Vec<_>>();
+
+        let adapter = TaggedAdapter::new();
+        let mut l = LinkedList::new(adapter);
+        let mut m = LinkedList::new(adapter);
+        let a = make(1);
+        let b = make(2);
+        let c = make(3);
+        let d = make(4);
+        l.push_back(a.clone());
+        l.push_back(b.clone());
+        l.push_back(c.clone());
+        m.push_back(d.clone());
+
+        assert!(m.remove_value(&a).is_none());
+        assert!(l.remove_value(&d).is_none());
+        assert_eq!(l.cursor_mut_for(&b).unwrap().get().unwrap().value, 2);
+        assert!(Rc::ptr_eq(&l.remove_value(&b).unwrap(), &b));
+        assert!(l.remove_value(&b).is_none());
+        assert_eq!(values(&l), [1, 3]);
+
+        // Elements keep belonging to the right list as they move around.
+        let mut n = l.take();
+        assert!(l.remove_value(&a).is_none());
+        l.push_back(b.clone());
+        assert!(n.remove_value(&b).is_none());
+        n.front_mut().splice_after(m);
+        assert_eq!(values(&n), [1, 4, 3]);
+        let mut o = n.front_mut().split_after();
+        assert!(n.remove_value(&d).is_none());
+        assert!(o.remove_value(&a).is_none());
+        assert!(Rc::ptr_eq(&o.remove_value(&d).unwrap(), &d));
+        assert_eq!(values(&n), [1]);
+        n.fast_clear();
+        assert!(n.remove_value(&a).is_none());
+        unsafe { a.link.force_unlink() };
+        n.push_back(a.clone());
+        assert!(Rc::ptr_eq(&n.remove_value(&a).unwrap(), &a));
+        assert_eq!(values(&o), [3]);
+        assert_eq!(values(&l), [2]);
+
+        // Empty lists split off from `l` don't share its owner tag.
+        let mut p = l.back_mut().split_after();
+        assert!(p.remove_value(&b).is_none());
+        let mut q = l.front_mut().split_before();
+        assert!(q.remove_value(&b).is_none());
+        assert_eq!(values(&l), [2]);
+
+        // Elements left behind by a panicking `dispose` don't belong to `l`.
+        l.push_back(a.clone());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| l.clear_and_dispose(|_| panic!())));
+        assert!(result.is_err());
+        assert!(l.is_empty());
+        assert!(a.link.is_linked());
+        assert!(l.remove_value(&a).is_none());
+    }
 }
@@ -0,0 +1,710 @@
+// Copyright 2020 Amari Robinson
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Container which keeps its elements in several intrusive collections at
+//! once.
+
+use core::fmt;
+use core::mem;
+use core::ptr::{self, NonNull};
+
+use crate::linked_list::{LinkedList, LinkedListOps};
+use crate::pointer_ops::{DefaultPointerOps, PointerOps};
+use crate::rbtree::{RBTree, RBTreeOps};
+use crate::Adapter;
+use crate::ComparatorAdapter;
+
+// =============================================================================
+// IndexRef
+// =============================================================================
+
+/// Pointer to an element of a `MultiIndex`, held by each of its indices.
+///
+/// The adapters of the indices must use this pointer type. Unlike `UnsafeRef`
+/// it can't be cloned, and can only be created by the `MultiIndex`, so an
+/// element can't be reached through a pointer taken out of an index after the
+/// `MultiIndex` has dropped it:
+///
+/// ```compile_fail
+/// use intrusive_collections::{intrusive_adapter, IndexRef, LinkedList, LinkedListLink, MultiIndex};
+///
+/// struct Obj {
+///     link: LinkedListLink,
+/// }
+/// intrusive_adapter!(ObjAdapter = IndexRef<Obj>: Obj { link: LinkedListLink });
+///
+/// let mut m = MultiIndex::<Box<Obj>, _>::new((LinkedList::new(ObjAdapter::new()),));
+/// m.insert(Box::new(Obj { link: LinkedListLink::new() }));
+/// let escaped = m.indices().0.front().clone_pointer();
+/// m.clear();
+/// ```
+pub struct IndexRef<T: ?Sized> {
+    ptr: NonNull<T>,
+}
+
+impl<T: ?Sized> IndexRef<T> {
+    // `raw` must point to an element of the `MultiIndex` which outlives the
+    // `IndexRef`.
+    #[inline]
+    unsafe fn from_raw(raw: *const T) -> IndexRef<T> {
+        IndexRef {
+            ptr: NonNull::new_unchecked(raw as *mut T),
+        }
+    }
+}
+
+unsafe impl<T: ?Sized + Send> Send for IndexRef<T> {}
+
+unsafe impl<T: ?Sized + Sync> Sync for IndexRef<T> {}
+
+unsafe impl<T: ?Sized> PointerOps for DefaultPointerOps<IndexRef<T>> {
+    type Value = T;
+    type Pointer = IndexRef<T>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> IndexRef<T> {
+        IndexRef::from_raw(raw)
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: IndexRef<T>) -> *const T {
+        ptr.ptr.as_ptr() as *const T
+    }
+}
+
+// =============================================================================
+// Index
+// =============================================================================
+
+/// An intrusive collection which can be used as an index of a `MultiIndex`.
+///
+/// The collection holds `IndexRef` pointers to the elements, which are owned
+/// by the `MultiIndex` itself.
+///
+/// # Safety
+///
+/// `link` must make the element reachable through the collection, and `first`
+/// must only return elements which were linked and not unlinked since.
+pub unsafe trait Index {
+    /// The type of the elements of the index.
+    type Value: ?Sized;
+
+    /// Inserts an element into the index.
+    ///
+    /// # Safety
+    ///
+    /// `value` must point to a valid object which outlives its membership of
+    /// the index.
+    unsafe fn link(&mut self, value: *const Self::Value);
+
+    /// Removes an element from the index.
+    ///
+    /// # Safety
+    ///
+    /// `value` must currently be linked into this index.
+    unsafe fn unlink(&mut self, value: *const Self::Value);
+
+    /// Moves an element to its correct position after its key has changed.
+    ///
+    /// # Safety
+    ///
+    /// `value` must currently be linked into this index.
+    unsafe fn relink(&mut self, value: *const Self::Value);
+
+    /// Empties the index without unlinking the elements in it.
+    fn fast_clear(&mut self);
+
+    /// Returns a reference to the first element of the index, or `None` if the
+    /// index is empty.
+    fn first(&self) -> Option<&Self::Value>;
+}
+
+// A `LinkedList` keeps the elements in insertion order, so new elements are
+// added at the back and a key change does not move them.
+unsafe impl<A, V: ?Sized> Index for LinkedList<A>
+where
+    A: Adapter<PointerOps = DefaultPointerOps<IndexRef<V>>>,
+    A::LinkOps: LinkedListOps,
+{
+    type Value = V;
+
+    #[inline]
+    unsafe fn link(&mut self, value: *const V) {
+        self.push_back(IndexRef::from_raw(value));
+    }
+
+    #[inline]
+    unsafe fn unlink(&mut self, value: *const V) {
+        self.cursor_mut_from_ptr(value).remove();
+    }
+
+    #[inline]
+    unsafe fn relink(&mut self, _value: *const V) {}
+
+    #[inline]
+    fn fast_clear(&mut self) {
+        LinkedList::fast_clear(self);
+    }
+
+    #[inline]
+    fn first(&self) -> Option<&V> {
+        self.front().get()
+    }
+}
+
+// An `RBTree` keeps the elements sorted by the key of its adapter.
+unsafe impl<A, V: ?Sized> Index for RBTree<A>
+where
+    A: Adapter<PointerOps = DefaultPointerOps<IndexRef<V>>> + for<'a> ComparatorAdapter<'a>,
+    A::LinkOps: RBTreeOps,
+{
+    type Value = V;
+
+    #[inline]
+    unsafe fn link(&mut self, value: *const V) {
+        self.insert(IndexRef::from_raw(value));
+    }
+
+    #[inline]
+    unsafe fn unlink(&mut self, value: *const V) {
+        self.cursor_mut_from_ptr(value).remove();
+    }
+
+    #[inline]
+    unsafe fn relink(&mut self, value: *const V) {
+        self.cursor_mut_from_ptr(value).update_key(|_| {});
+    }
+
+    #[inline]
+    fn fast_clear(&mut self) {
+        RBTree::fast_clear(self);
+    }
+
+    #[inline]
+    fn first(&self) -> Option<&V> {
+        self.front().get()
+    }
+}
+
+// =============================================================================
+// Indices
+// =============================================================================
+
+/// A tuple of indices over the same element type, as held by a `MultiIndex`.
+///
+/// This is implemented for tuples of up to 4 `Index` types.
+///
+/// # Safety
+///
+/// Each operation must apply the corresponding `Index` operation to every
+/// index of the tuple.
+pub unsafe trait Indices {
+    /// The type of the elements of the indices.
+    type Value: ?Sized;
+
+    /// Inserts an element into all indices.
+    ///
+    /// # Safety
+    ///
+    /// See `Index::link`.
+    unsafe fn link(&mut self, value: *const Self::Value);
+
+    /// Removes an element from all indices.
+    ///
+    /// # Safety
+    ///
+    /// See `Index::unlink`.
+    unsafe fn unlink(&mut self, value: *const Self::Value);
+
+    /// Repositions an element in all indices after its keys have changed.
+    ///
+    /// # Safety
+    ///
+    /// See `Index::relink`.
+    unsafe fn relink(&mut self, value: *const Self::Value);
+
+    /// Empties all indices without unlinking the elements in them.
+    fn fast_clear(&mut self);
+
+    /// Returns a reference to the first element of the first index, or `None`
+    /// if the indices are empty.
+    fn first(&self) -> Option<&Self::Value>;
+
+    /// Returns `true` if all indices are empty.
+    fn is_empty(&self) -> bool;
+
+    /// Returns `true` if `collection` is the address of one of the indices.
+    fn is_index(&self, collection: *const ()) -> bool;
+}
+
+macro_rules! impl_indices {
+    ($first:ident $($name:ident $index:tt)*) => {
+        unsafe impl<V: ?Sized, $first, $($name),*> Indices for ($first, $($name,)*)
+        where
+            $first: Index<Value = V>,
+            $($name: Index<Value = V>,)*
+        {
+            type Value = V;
+
+            #[inline]
+            unsafe fn link(&mut self, value: *const V) {
+                self.0.link(value);
+                $(self.$index.link(value);)*
+            }
+
+            #[inline]
+            unsafe fn unlink(&mut self, value: *const V) {
+                self.0.unlink(value);
+                $(self.$index.unlink(value);)*
+            }
+
+            #[inline]
+            unsafe fn relink(&mut self, value: *const V) {
+                self.0.relink(value);
+                $(self.$index.relink(value);)*
+            }
+
+            #[inline]
+            fn fast_clear(&mut self) {
+                self.0.fast_clear();
+                $(self.$index.fast_clear();)*
+            }
+
+            #[inline]
+            fn first(&self) -> Option<&V> {
+                self.0.first()
+            }
+
+            #[inline]
+            fn is_empty(&self) -> bool {
+                self.0.first().is_none() $(&& self.$index.first().is_none())*
+            }
+
+            #[inline]
+            fn is_index(&self, collection: *const ()) -> bool {
+                ptr::eq(&self.0 as *const $first as *const (), collection)
+                    $(|| ptr::eq(&self.$index as *const $name as *const (), collection))*
+            }
+        }
+    };
+}
+
+impl_indices!(I0);
+impl_indices!(I0 I1 1);
+impl_indices!(I0 I1 1 I2 2);
+impl_indices!(I0 I1 1 I2 2 I3 3);
+
+// If an index panics half way through an operation then the indices no longer
+// agree on which elements they contain. Forget all of them, which leaks the
+// elements but keeps the container safe to use.
+struct PanicGuard<'a, I: Indices>(&'a mut I);
+
+impl<'a, I: Indices> Drop for PanicGuard<'a, I> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.fast_clear();
+    }
+}
+
+impl<'a, I: Indices> PanicGuard<'a, I> {
+    #[inline]
+    fn finish(self) {
+        mem::forget(self);
+    }
+}
+
+// =============================================================================
+// Position
+// =============================================================================
+
+/// A position in one of the indices of a `MultiIndex`, which picks the element
+/// to remove or modify.
+///
+/// A `Position` is created from a `Cursor` of a `LinkedList` or an `RBTree`
+/// with `From`. It remembers which collection the cursor belongs to, so that
+/// the `MultiIndex` can check that the element is really one of its own.
+pub struct Position<'a, V: ?Sized> {
+    collection: *const (),
+    value: Option<&'a V>,
+}
+
+impl<'a, V: ?Sized> Position<'a, V> {
+    // Creates a position for an element of the collection at the given
+    // address. `value` must be an element of that collection.
+    #[inline]
+    pub(crate) unsafe fn new(collection: *const (), value: Option<&'a V>) -> Position<'a, V> {
+        Position { collection, value }
+    }
+
+    /// Returns a reference to the element at this position, or `None` if the
+    /// cursor it was created from was pointing to the null object.
+    #[inline]
+    pub fn get(&self) -> Option<&'a V> {
+        self.value
+    }
+}
+
+// =============================================================================
+// MultiIndex
+// =============================================================================
+
+/// A container which keeps each of its elements in several intrusive
+/// collections, called indices, at the same time.
+///
+/// The container owns one pointer of type `P` to each element, while the
+/// indices hold `IndexRef`s to it. Elements are inserted into, removed from
+/// and repositioned in all indices at once, so the indices can never disagree
+/// on which elements they contain.
+///
+/// The indices are given as a tuple of `LinkedList`s, which keep insertion
+/// order, and `RBTree`s, which keep the elements sorted by key. They can be
+/// read through `indices`, for example to iterate over them or to look up an
+/// element with a cursor. Elements are removed or modified through a closure
+/// which is given the indices and returns a cursor of one of them, converted
+/// into a `Position`.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::{intrusive_adapter, KeyAdapter};
+/// use intrusive_collections::{LinkedList, LinkedListLink, MultiIndex, RBTree, RBTreeLink};
+/// use intrusive_collections::IndexRef;
+/// use std::cell::Cell;
+///
+/// struct Task {
+///     lru_link: LinkedListLink,
+///     id_link: RBTreeLink,
+///     deadline_link: RBTreeLink,
+///     id: u32,
+///     deadline: Cell<u64>,
+/// }
+///
+/// intrusive_adapter!(Lru = IndexRef<Task>: Task { lru_link: LinkedListLink });
+/// intrusive_adapter!(ById = IndexRef<Task>: Task { id_link: RBTreeLink });
+/// intrusive_adapter!(ByDeadline = IndexRef<Task>: Task { deadline_link: RBTreeLink });
+/// impl<'a> KeyAdapter<'a> for ById {
+///     type Key = u32;
+///     fn get_key(&self, task: &'a Task) -> u32 {
+///         task.id
+///     }
+/// }
+/// impl<'a> KeyAdapter<'a> for ByDeadline {
+///     type Key = u64;
+///     fn get_key(&self, task: &'a Task) -> u64 {
+///         task.deadline.get()
+///     }
+/// }
+///
+/// let mut tasks = MultiIndex::<Box<Task>, _>::new((
+///     LinkedList::new(Lru::new()),
+///     RBTree::new(ById::new()),
+///     RBTree::new(ByDeadline::new()),
+/// ));
+/// for (id, deadline) in [(1, 30), (2, 10), (3, 20)].iter().cloned() {
+///     tasks.insert(Box::new(Task {
+///         lru_link: LinkedListLink::new(),
+///         id_link: RBTreeLink::new(),
+///         deadline_link: RBTreeLink::new(),
+///         id,
+///         deadline: Cell::new(deadline),
+///     }));
+/// }
+///
+/// // Postpone task 2, which repositions it in the deadline index.
+/// tasks.modify(|(_, by_id, _)| by_id.find(&2).into(), |task| task.deadline.set(40));
+/// let (_, _, by_deadline) = tasks.indices();
+/// assert_eq!(by_deadline.iter().map(|task| task.id).collect::<Vec<_>>(), [3, 1, 2]);
+///
+/// // Remove the task with the earliest deadline from all indices.
+/// let task = tasks.remove(|(_, _, by_deadline)| by_deadline.front().into()).unwrap();
+/// assert_eq!(task.id, 3);
+/// let (lru, _, _) = tasks.indices();
+/// assert_eq!(lru.iter().map(|task| task.id).collect::<Vec<_>>(), [1, 2]);
+/// ```
+pub struct MultiIndex<P, I: Indices>
+where
+    DefaultPointerOps<P>: PointerOps<Pointer = P, Value = I::Value>,
+{
+    indices: I,
+    pointer_ops: DefaultPointerOps<P>,
+}
+
+impl<P, I: Indices> MultiIndex<P, I>
+where
+    DefaultPointerOps<P>: PointerOps<Pointer = P, Value = I::Value>,
+{
+    /// Creates an empty `MultiIndex` from a tuple of empty indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the indices is not empty.
+    #[inline]
+    pub fn new(indices: I) -> MultiIndex<P, I> {
+        assert!(
+            indices.is_empty(),
+            "the indices of a new MultiIndex must be empty"
+        );
+        MultiIndex {
+            indices,
+            pointer_ops: DefaultPointerOps::new(),
+        }
+    }
+
+    /// Returns a reference to the tuple of indices.
+    ///
+    /// This gives read-only access to each index, including its iterators and
+    /// cursors. References to the elements borrow the `MultiIndex`, and since
+    /// `IndexRef` can't be cloned no pointer to an element can be taken out of
+    /// an index.
+    #[inline]
+    pub fn indices(&self) -> &I {
+        &self.indices
+    }
+
+    /// Returns `true` if the `MultiIndex` is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.indices.first().is_none()
+    }
+
+    /// Inserts a new element into all indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new element is already linked to a different intrusive
+    /// collection. If this happens the `MultiIndex` is emptied and all of its
+    /// elements are leaked.
+    #[inline]
+    pub fn insert(&mut self, val: P) {
+        let value = self.pointer_ops.into_raw(val);
+        let guard = PanicGuard(&mut self.indices);
+        unsafe {
+            guard.0.link(value);
+        }
+        guard.finish();
+    }
+
+    // Returns the element at the position picked by `select`, after checking
+    // that the position belongs to one of the indices.
+    #[inline]
+    fn select<F>(&self, select: F) -> Option<*const I::Value>
+    where
+        F: FnOnce(&I) -> Position<'_, I::Value>,
+    {
+        let position = select(&self.indices);
+        assert!(
+            self.indices.is_index(position.collection),
+            "position does not belong to an index of this MultiIndex"
+        );
+        position.value.map(|value| value as *const I::Value)
+    }
+
+    /// Removes the element picked by `select` from all indices and returns a
+    /// pointer to it.
+    ///
+    /// `select` is given the tuple of indices and returns a position in one of
+    /// them. If the position is null then `None` is returned and the
+    /// `MultiIndex` is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position does not belong to one of the indices.
+    #[inline]
+    pub fn remove<F>(&mut self, select: F) -> Option<P>
+    where
+        F: FnOnce(&I) -> Position<'_, I::Value>,
+    {
+        let value = self.select(select)?;
+        let guard = PanicGuard(&mut self.indices);
+        unsafe {
+            guard.0.unlink(value);
+            guard.finish();
+            Some(self.pointer_ops.from_raw(value))
+        }
+    }
+
+    /// Runs `f` on the element picked by `select`, and then repositions the
+    /// element in every index, so that `f` may change any of its keys.
+    ///
+    /// Returns `false` if the position picked by `select` is null, in which
+    /// case `f` is not called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position does not belong to one of the indices.
+    ///
+    /// If an index panics while repositioning the element, for example
+    /// because a comparator panicked, the `MultiIndex` is emptied and all of
+    /// its elements are leaked.
+    #[inline]
+    pub fn modify<F, G>(&mut self, select: F, f: G) -> bool
+    where
+        F: FnOnce(&I) -> Position<'_, I::Value>,
+        G: FnOnce(&I::Value),
+    {
+        let value = match self.select(select) {
+            Some(value) => value,
+            None => return false,
+        };
+        unsafe {
+            f(&*value);
+        }
+        let guard = PanicGuard(&mut self.indices);
+        unsafe {
+            guard.0.relink(value);
+        }
+        guard.finish();
+        true
+    }
+
+    /// Removes all elements from the `MultiIndex`.
+    ///
+    /// This will unlink all objects currently in the indices, which requires
+    /// iterating through all elements.
+    #[inline]
+    pub fn clear(&mut self) {
+        while let Some(value) = self.indices.first() {
+            let value = value as *const I::Value;
+            unsafe {
+                self.indices.unlink(value);
+                drop(self.pointer_ops.from_raw(value));
+            }
+        }
+    }
+}
+
+// Drop all owned pointers if the collection is dropped
+impl<P, I: Indices> Drop for MultiIndex<P, I>
+where
+    DefaultPointerOps<P>: PointerOps<Pointer = P, Value = I::Value>,
+{
+    #[inline]
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<P, I: Indices + Default> Default for MultiIndex<P, I>
+where
+    DefaultPointerOps<P>: PointerOps<Pointer = P, Value = I::Value>,
+{
+    #[inline]
+    fn default() -> MultiIndex<P, I> {
+        MultiIndex::new(I::default())
+    }
+}
+
+impl<P, I: Indices + fmt::Debug> fmt::Debug for MultiIndex<P, I>
+where
+    DefaultPointerOps<P>: PointerOps<Pointer = P, Value = I::Value>,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.indices.fmt(f)
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexRef, MultiIndex};
+    use crate::{KeyAdapter, LinkedList, LinkedListLink, RBTree, RBTreeLink};
+    use std::boxed::Box;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::vec;
+    use std::vec::Vec;
+
+    struct Obj {
+        list_link: LinkedListLink,
+        tree_link: RBTreeLink,
+        key: Cell<i32>,
+        dropped: Rc<Cell<u32>>,
+    }
+    impl Drop for Obj {
+        fn drop(&mut self) {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+    intrusive_adapter!(ListAdapter = IndexRef<Obj>: Obj { list_link: LinkedListLink });
+    intrusive_adapter!(TreeAdapter = IndexRef<Obj>: Obj { tree_link: RBTreeLink });
+    impl<'a> KeyAdapter<'a> for TreeAdapter {
+        type Key = i32;
+        fn get_key(&self, value: &'a Obj) -> i32 {
+            value.key.get()
+        }
+    }
+    type Container = MultiIndex<Box<Obj>, (LinkedList<ListAdapter>, RBTree<TreeAdapter>)>;
+
+    fn keys(m: &Container) -> (Vec<i32>, Vec<i32>) {
+        let (list, tree) = m.indices();
+        (
+            list.iter().map(|x| x.key.get()).collect(),
+            tree.iter().map(|x| x.key.get()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_multi_index() {
+        let dropped = Rc::new(Cell::new(0));
+        let mut m = Container::default();
+        assert!(m.is_empty());
+        for &key in [3, 1, 2, 5].iter() {
+            m.insert(Box::new(Obj {
+                list_link: LinkedListLink::new(),
+                tree_link: RBTreeLink::new(),
+                key: Cell::new(key),
+                dropped: dropped.clone(),
+            }));
+        }
+        assert_eq!(keys(&m), (vec![3, 1, 2, 5], vec![1, 2, 3, 5]));
+
+        assert!(m.modify(|(_, tree)| tree.find(&1).into(), |x| x.key.set(4)));
+        assert!(!m.modify(|(_, tree)| tree.find(&1).into(), |_| unreachable!()));
+        assert_eq!(keys(&m), (vec![3, 4, 2, 5], vec![2, 3, 4, 5]));
+
+        let x = m.remove(|(list, _)| list.front().into()).unwrap();
+        assert_eq!(x.key.get(), 3);
+        assert!(!x.list_link.is_linked());
+        assert!(!x.tree_link.is_linked());
+        assert!(m.remove(|(_, tree)| tree.find(&3).into()).is_none());
+        assert_eq!(keys(&m), (vec![4, 2, 5], vec![2, 4, 5]));
+        drop(x);
+        assert_eq!(dropped.get(), 1);
+
+        m.clear();
+        assert!(m.is_empty());
+        assert_eq!(dropped.get(), 4);
+        m.insert(Box::new(Obj {
+            list_link: LinkedListLink::new(),
+            tree_link: RBTreeLink::new(),
+            key: Cell::new(0),
+            dropped: dropped.clone(),
+        }));
+        drop(m);
+        assert_eq!(dropped.get(), 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_foreign_position() {
+        let other = Box::leak(Box::new(LinkedList::new(ListAdapter::new())));
+        let obj = Box::new(Obj {
+            list_link: LinkedListLink::new(),
+            tree_link: RBTreeLink::new(),
+            key: Cell::new(0),
+            dropped: Rc::new(Cell::new(0)),
+        });
+        other.push_back(unsafe { IndexRef::from_raw(Box::into_raw(obj)) });
+        let other: &'static LinkedList<ListAdapter> = other;
+        let mut m = Container::default();
+        m.remove(move |_| other.front().into());
+    }
+}
@@ -11,10 +11,15 @@ use crate::alloc::boxed::Box;
 use crate::alloc::rc::Rc;
 #[cfg(feature = "alloc")]
 use crate::alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use crate::intrusive_arc::{IntrusiveArc, RefCounted};
 use crate::UnsafeRef;
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+use core::alloc::Allocator;
 use core::marker::PhantomData;
 use core::mem::ManuallyDrop;
 use core::ops::Deref;
+use core::pin::Pin;
 
 /// Trait for pointer conversion operations.
 ///
@@ -147,6 +152,282 @@ unsafe impl<T: ?Sized> PointerOps for DefaultPointerOps<Arc<T>> {
     }
 }
 
+/// Marker trait for `PointerOps` whose pointers are the only way to access
+/// the value they point to, such as `Box<T>`.
+///
+/// Values in collections using such pointers can be partially mutated through
+/// a `CursorMut`, see `DataMut`. Note that this relies on `UnsafeRef::from_raw`
+/// not being used to create other pointers to a value owned by a `Box` while
+/// it is in such a collection.
+///
+/// A custom `PointerOps` for `UnsafeRef` may implement this trait if all of
+/// its pointers are created from a unique source such as `UnsafeRef::from_box`
+/// and never cloned.
+///
+/// # Safety
+///
+/// While a value is owned by a collection, the pointer passed to `into_raw`
+/// must be the only way to access it.
+pub unsafe trait UniquePointerOps: PointerOps {}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized> UniquePointerOps for DefaultPointerOps<Box<T>> {}
+
+/// Trait for objects containing data which can be mutated without affecting
+/// their links.
+///
+/// This gives `CursorMut::get_data_mut` a mutable reference to the data of an
+/// element of a collection whose `PointerOps` implement `UniquePointerOps`.
+/// Since the data may include the key of the element, an `RBTree` instead
+/// provides `CursorMut::update_data`, which repositions the element afterwards.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::{intrusive_adapter, DataMut, LinkedList, LinkedListLink};
+///
+/// struct Task {
+///     link: LinkedListLink,
+///     data: TaskData,
+/// }
+///
+/// struct TaskData {
+///     runs: u32,
+/// }
+///
+/// unsafe impl DataMut for Task {
+///     type Data = TaskData;
+///     fn data_mut(&mut self) -> &mut TaskData {
+///         &mut self.data
+///     }
+/// }
+///
+/// intrusive_adapter!(TaskAdapter = Box<Task>: Task { link: LinkedListLink });
+///
+/// let mut list = LinkedList::new(TaskAdapter::new());
+/// list.push_back(Box::new(Task { link: LinkedListLink::new(), data: TaskData { runs: 0 } }));
+/// list.front_mut().get_data_mut().unwrap().runs += 1;
+/// assert_eq!(list.front().get().unwrap().data.runs, 1);
+/// ```
+///
+/// # Safety
+///
+/// The reference returned by `data_mut` must point into `self` and must not
+/// overlap any of the links of `self`.
+pub unsafe trait DataMut {
+    /// Type of the data which can be mutated.
+    type Data: ?Sized;
+
+    /// Returns a mutable reference to the data of `self`.
+    fn data_mut(&mut self) -> &mut Self::Data;
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: RefCounted> PointerOps for DefaultPointerOps<IntrusiveArc<T>> {
+    type Value = T;
+    type Pointer = IntrusiveArc<T>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> IntrusiveArc<T> {
+        IntrusiveArc::from_raw(raw)
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: IntrusiveArc<T>) -> *const T {
+        IntrusiveArc::into_raw(ptr)
+    }
+}
+
+/// Marker trait for `PointerOps` whose pointers keep the value they point to
+/// pinned, such as `Pin<Box<T>>`.
+///
+/// Values in collections using such pointers can be accessed through
+/// `Pin<&T>`, for example with the `get_pin` method of cursors.
+///
+/// # Safety
+///
+/// Every value converted by `into_raw` must be pinned, and `from_raw` must
+/// return a pointer which keeps it pinned.
+pub unsafe trait PinnedPointerOps: PointerOps {}
+
+unsafe impl<'a, T: ?Sized> PointerOps for DefaultPointerOps<Pin<&'a T>> {
+    type Value = T;
+    type Pointer = Pin<&'a T>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> Pin<&'a T> {
+        Pin::new_unchecked(&*raw)
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: Pin<&'a T>) -> *const T {
+        unsafe { Pin::into_inner_unchecked(ptr) as *const T }
+    }
+}
+
+unsafe impl<T: ?Sized> PinnedPointerOps for DefaultPointerOps<Pin<&T>> {}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized> PointerOps for DefaultPointerOps<Pin<Box<T>>> {
+    type Value = T;
+    type Pointer = Pin<Box<T>>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> Pin<Box<T>> {
+        Pin::new_unchecked(Box::from_raw(raw as *mut T))
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: Pin<Box<T>>) -> *const T {
+        Box::into_raw(unsafe { Pin::into_inner_unchecked(ptr) }) as *const T
+    }
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized> PinnedPointerOps for DefaultPointerOps<Pin<Box<T>>> {}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized> PointerOps for DefaultPointerOps<Pin<Rc<T>>> {
+    type Value = T;
+    type Pointer = Pin<Rc<T>>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> Pin<Rc<T>> {
+        Pin::new_unchecked(Rc::from_raw(raw))
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: Pin<Rc<T>>) -> *const T {
+        Rc::into_raw(unsafe { Pin::into_inner_unchecked(ptr) })
+    }
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized> PinnedPointerOps for DefaultPointerOps<Pin<Rc<T>>> {}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized> PointerOps for DefaultPointerOps<Pin<Arc<T>>> {
+    type Value = T;
+    type Pointer = Pin<Arc<T>>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> Pin<Arc<T>> {
+        Pin::new_unchecked(Arc::from_raw(raw))
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: Pin<Arc<T>>) -> *const T {
+        Arc::into_raw(unsafe { Pin::into_inner_unchecked(ptr) })
+    }
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized> PinnedPointerOps for DefaultPointerOps<Pin<Arc<T>>> {}
+
+/// `PointerOps` for pointers using a custom allocator, such as `Box<T, A>`.
+///
+/// The allocator is stored in the `PointerOps` so that `from_raw` can rebuild
+/// the pointer with it. This requires an allocator which is cheap to clone,
+/// such as a reference to an arena.
+///
+/// Pointers given to `into_raw` are turned back into pointers using the stored
+/// allocator, so they must have been allocated by that allocator or one which
+/// can free its memory, such as a clone of it. This is why `new` is unsafe.
+///
+/// Since `intrusive_adapter!` always uses `DefaultPointerOps`, an adapter
+/// using `AllocPointerOps` must implement `Adapter` manually.
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+pub struct AllocPointerOps<Pointer, A> {
+    alloc: A,
+    _marker: PhantomData<Pointer>,
+}
+
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+impl<Pointer, A> AllocPointerOps<Pointer, A> {
+    /// Constructs an instance of `AllocPointerOps` using the given allocator.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer passed to `into_raw` must have been allocated by `alloc`,
+    /// or by an allocator which can free memory allocated by `alloc`.
+    #[inline]
+    pub const unsafe fn new(alloc: A) -> AllocPointerOps<Pointer, A> {
+        AllocPointerOps {
+            alloc,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the allocator.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+}
+
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+impl<Pointer, A: Clone> Clone for AllocPointerOps<Pointer, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        // A clone of the allocator can free the same memory.
+        unsafe { AllocPointerOps::new(self.alloc.clone()) }
+    }
+}
+
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+impl<Pointer, A: Copy> Copy for AllocPointerOps<Pointer, A> {}
+
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+unsafe impl<T: ?Sized, A: Allocator + Clone> PointerOps for AllocPointerOps<Box<T, A>, A> {
+    type Value = T;
+    type Pointer = Box<T, A>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> Box<T, A> {
+        Box::from_raw_in(raw as *mut T, self.alloc.clone())
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: Box<T, A>) -> *const T {
+        Box::into_raw_with_allocator(ptr).0 as *const T
+    }
+}
+
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+unsafe impl<T: ?Sized, A: Allocator + Clone> UniquePointerOps for AllocPointerOps<Box<T, A>, A> {}
+
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+unsafe impl<T: ?Sized, A: Allocator + Clone> PointerOps for AllocPointerOps<Rc<T, A>, A> {
+    type Value = T;
+    type Pointer = Rc<T, A>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> Rc<T, A> {
+        Rc::from_raw_in(raw, self.alloc.clone())
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: Rc<T, A>) -> *const T {
+        Rc::into_raw_with_allocator(ptr).0
+    }
+}
+
+#[cfg(all(feature = "nightly", feature = "alloc"))]
+unsafe impl<T: ?Sized, A: Allocator + Clone> PointerOps for AllocPointerOps<Arc<T, A>, A> {
+    type Value = T;
+    type Pointer = Arc<T, A>;
+
+    #[inline]
+    unsafe fn from_raw(&self, raw: *const T) -> Arc<T, A> {
+        Arc::from_raw_in(raw, self.alloc.clone())
+    }
+
+    #[inline]
+    fn into_raw(&self, ptr: Arc<T, A>) -> *const T {
+        Arc::into_raw_with_allocator(ptr).0
+    }
+}
+
 /// Clones a `PointerOps::Pointer` from a `*const PointerOps::Value`
 ///
 /// This method is only safe to call if the raw pointer is known to be
@@ -192,6 +473,7 @@ mod tests {
     use std::boxed::Box;
     use std::fmt::Debug;
     use std::mem;
+    use std::pin::Pin;
     use std::rc::Rc;
     use std::sync::Arc;
 
@@ -288,6 +570,151 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pin() {
+        unsafe {
+            let pointer_ops = DefaultPointerOps::<Pin<Box<_>>>::new();
+            let p = Box::pin(1);
+            let a: *const i32 = &*p;
+            let r = pointer_ops.into_raw(p);
+            assert_eq!(a, r);
+            let p2: Pin<Box<i32>> = pointer_ops.from_raw(r);
+            assert_eq!(a, &*p2 as *const i32);
+
+            let pointer_ops = DefaultPointerOps::<Pin<Rc<_>>>::new();
+            let p = Rc::pin(2) as Pin<Rc<dyn Debug>>;
+            let a: *const dyn Debug = &*p;
+            let r = pointer_ops.into_raw(p);
+            assert_eq!(a, r);
+            let p2: Pin<Rc<dyn Debug>> = pointer_ops.from_raw(r);
+            assert_eq!(a, &*p2 as *const dyn Debug);
+
+            let pointer_ops = DefaultPointerOps::<Pin<Arc<_>>>::new();
+            let p = Arc::pin(3);
+            let a: *const i32 = &*p;
+            let r = pointer_ops.into_raw(p);
+            assert_eq!(a, r);
+            let p2: Pin<Arc<i32>> = pointer_ops.from_raw(r);
+            assert_eq!(a, &*p2 as *const i32);
+
+            let pointer_ops = DefaultPointerOps::<Pin<&_>>::new();
+            let x = 4;
+            let r = pointer_ops.into_raw(Pin::new(&x));
+            assert_eq!(r, &x as *const i32);
+            assert_eq!(*pointer_ops.from_raw(r), 4);
+        }
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn test_alloc() {
+        use super::AllocPointerOps;
+        use crate::{container_of, Adapter, DefaultLinkOps, LinkOps, LinkedList, LinkedListLink};
+        use std::alloc::{AllocError, Allocator, Global, Layout};
+        use std::cell::Cell;
+        use std::ptr::NonNull;
+
+        // Allocator which counts the allocations which are still alive.
+        #[derive(Clone, Copy)]
+        struct Counting<'a>(&'a Cell<usize>);
+        unsafe impl Allocator for Counting<'_> {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.0.set(self.0.get() + 1);
+                Global.allocate(layout)
+            }
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.0.set(self.0.get() - 1);
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        let count = Cell::new(0);
+        let alloc = Counting(&count);
+        unsafe {
+            let pointer_ops = AllocPointerOps::<Box<_, _>, _>::new(alloc);
+            let p = Box::new_in(1, alloc);
+            let a: *const i32 = &*p;
+            let r = pointer_ops.into_raw(p);
+            assert_eq!(a, r);
+            assert_eq!(count.get(), 1);
+            let p2: Box<i32, _> = pointer_ops.from_raw(r);
+            assert_eq!(a, &*p2 as *const i32);
+            drop(p2);
+            assert_eq!(count.get(), 0);
+
+            let pointer_ops = AllocPointerOps::<Rc<_, _>, _>::new(alloc);
+            let p = Rc::new_in(2, alloc) as Rc<dyn Debug, _>;
+            let r = pointer_ops.into_raw(p);
+            let p2: Rc<dyn Debug, _> = pointer_ops.from_raw(r);
+            assert_eq!(r, &*p2 as *const dyn Debug);
+            drop(p2);
+            assert_eq!(count.get(), 0);
+
+            let pointer_ops = AllocPointerOps::<Arc<_, _>, _>::new(alloc);
+            let p = Arc::new_in(3, alloc);
+            let r = pointer_ops.into_raw(p);
+            let p2: Arc<i32, _> = pointer_ops.from_raw(r);
+            assert_eq!(*p2, 3);
+            drop(p2);
+            assert_eq!(count.get(), 0);
+        }
+
+        // Elements of a collection are freed with the adapter's allocator.
+        struct Obj {
+            link: LinkedListLink,
+            value: i32,
+        }
+        struct ObjAdapter<'a> {
+            link_ops: <LinkedListLink as DefaultLinkOps>::Ops,
+            pointer_ops: AllocPointerOps<Box<Obj, Counting<'a>>, Counting<'a>>,
+        }
+        unsafe impl<'a> Adapter for ObjAdapter<'a> {
+            type LinkOps = <LinkedListLink as DefaultLinkOps>::Ops;
+            type PointerOps = AllocPointerOps<Box<Obj, Counting<'a>>, Counting<'a>>;
+
+            unsafe fn get_value(&self, link: <Self::LinkOps as LinkOps>::LinkPtr) -> *const Obj {
+                container_of!(link.as_ptr(), Obj, link)
+            }
+            unsafe fn get_link(&self, value: *const Obj) -> <Self::LinkOps as LinkOps>::LinkPtr {
+                NonNull::from(&(*value).link)
+            }
+            fn link_ops(&self) -> &Self::LinkOps {
+                &self.link_ops
+            }
+            fn link_ops_mut(&mut self) -> &mut Self::LinkOps {
+                &mut self.link_ops
+            }
+            fn pointer_ops(&self) -> &Self::PointerOps {
+                &self.pointer_ops
+            }
+        }
+
+        let make = |value| {
+            Box::new_in(
+                Obj {
+                    link: LinkedListLink::new(),
+                    value,
+                },
+                alloc,
+            )
+        };
+        let mut l = LinkedList::new(ObjAdapter {
+            link_ops: LinkedListLink::NEW,
+            pointer_ops: unsafe { AllocPointerOps::new(alloc) },
+        });
+        l.push_back(make(1));
+        l.push_back(make(2));
+        l.push_front(make(3));
+        assert_eq!(count.get(), 3);
+        let p = l.pop_front().unwrap();
+        assert_eq!(p.value, 3);
+        drop(p);
+        assert_eq!(count.get(), 2);
+        assert_eq!(l.back().get().unwrap().value, 2);
+        l.clear();
+        assert_eq!(count.get(), 0);
+    }
+
     #[test]
     fn clone_arc_from_raw() {
         use super::clone_pointer_from_raw;
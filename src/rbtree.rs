@@ -8,23 +8,27 @@
 
 //! Intrusive red-black tree.
 
-use core::borrow::Borrow;
 use core::cell::Cell;
 use core::cmp::Ordering;
 use core::fmt;
-use core::mem;
-use core::ptr::NonNull;
+use core::iter::Peekable;
+use core::mem::{self, ManuallyDrop};
+use core::ops::RangeBounds;
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
 
 use crate::Bound::{self, Excluded, Included, Unbounded};
 
-use crate::link_ops::{self, DefaultLinkOps};
-use crate::linked_list::LinkedListOps;
-use crate::pointer_ops::PointerOps;
+use crate::compare::{Compare, CompareWith};
+use crate::link_ops::{self, DefaultLinkOps, OwnerLinkOps};
+use crate::linked_list::{LinkedList, LinkedListOps};
+use crate::multi_index::Position;
+use crate::pointer_ops::{DataMut, PinnedPointerOps, PointerOps, UniquePointerOps};
 use crate::singly_linked_list::SinglyLinkedListOps;
 use crate::unchecked_option::UncheckedOptionExt;
 use crate::xor_linked_list::XorLinkedListOps;
 use crate::Adapter;
-use crate::KeyAdapter;
+use crate::ComparatorAdapter;
 
 // =============================================================================
 // RBTreeOps
@@ -146,6 +150,19 @@ impl DefaultLinkOps for Link {
 // An object containing a link can be sent to another thread if it is unlinked.
 unsafe impl Send for Link {}
 
+// Freeing an object which is still linked leaves a dangling pointer in the
+// collection, so turn this into a panic when debugging.
+#[cfg(feature = "debug-unsafe-ref")]
+impl Drop for Link {
+    #[inline]
+    fn drop(&mut self) {
+        assert!(
+            !self.is_linked(),
+            "`RBTreeLink` dropped while still linked into a `RBTree`"
+        );
+    }
+}
+
 // Provide an implementation of Clone which simply initializes the new link as
 // unlinked. This allows structs containing a link to derive Clone.
 impl Clone for Link {
@@ -529,11 +546,13 @@ unsafe fn rotate_right<T: RBTreeOps>(
 }
 
 // This code is based on the red-black tree implementation in libc++
+//
+// Returns `true` if the black height of the tree was increased.
 unsafe fn post_insert<T: RBTreeOps>(
     link_ops: &mut T,
     ptr: T::LinkPtr,
     root: &mut Option<T::LinkPtr>,
-) {
+) -> bool {
     let mut x = ptr;
     while let Some(parent) = link_ops.parent(x) {
         if link_ops.color(parent) != Color::Red {
@@ -552,6 +571,8 @@ unsafe fn post_insert<T: RBTreeOps>(
 
                     if link_ops.parent(x).is_none() {
                         link_ops.set_color(x, Color::Black);
+                        link_ops.set_color(y, Color::Black);
+                        return true;
                     } else {
                         link_ops.set_color(x, Color::Red);
                     }
@@ -578,6 +599,8 @@ unsafe fn post_insert<T: RBTreeOps>(
                     x = grandparent;
                     if link_ops.parent(x).is_none() {
                         link_ops.set_color(x, Color::Black);
+                        link_ops.set_color(y, Color::Black);
+                        return true;
                     } else {
                         link_ops.set_color(x, Color::Red);
                     }
@@ -597,6 +620,7 @@ unsafe fn post_insert<T: RBTreeOps>(
             break;
         }
     }
+    false
 }
 
 // This code is based on the red-black tree implementation in libc++
@@ -749,6 +773,290 @@ unsafe fn remove<T: RBTreeOps>(link_ops: &mut T, ptr: T::LinkPtr, root: &mut Opt
     link_ops.release_link(ptr);
 }
 
+// Builds a balanced subtree out of the next `len` nodes of a chain threaded
+// through the right pointers, advancing `head` past the consumed nodes. The
+// left and right subtrees of every node differ in size by at most one, so all
+// levels except the last are full. Nodes on the last level (`red_depth`) are
+// colored red and all others black, which gives a valid red-black tree.
+unsafe fn build_balanced<T: RBTreeOps>(
+    link_ops: &mut T,
+    head: &mut Option<T::LinkPtr>,
+    len: usize,
+    depth: usize,
+    red_depth: usize,
+) -> Option<T::LinkPtr> {
+    if len == 0 {
+        return None;
+    }
+    let left_len = (len - 1) / 2;
+    let left = build_balanced(link_ops, head, left_len, depth + 1, red_depth);
+    let node = head.unwrap_unchecked();
+    *head = link_ops.right(node);
+    let right = build_balanced(link_ops, head, len - 1 - left_len, depth + 1, red_depth);
+
+    link_ops.set_left(node, left);
+    if let Some(left) = left {
+        link_ops.set_parent(left, Some(node));
+    }
+    link_ops.set_right(node, right);
+    if let Some(right) = right {
+        link_ops.set_parent(right, Some(node));
+    }
+    let color = if depth == red_depth {
+        Color::Red
+    } else {
+        Color::Black
+    };
+    link_ops.set_color(node, color);
+    Some(node)
+}
+
+// Turns a chain of `len` nodes threaded through the right pointers into a
+// balanced red-black tree and returns its root.
+unsafe fn build_from_chain<T: RBTreeOps>(
+    link_ops: &mut T,
+    head: Option<T::LinkPtr>,
+    len: usize,
+) -> Option<T::LinkPtr> {
+    // Depth of the first level that is not completely filled.
+    let red_depth = mem::size_of::<usize>() * 8 - 1 - (len + 1).leading_zeros() as usize;
+    let mut head = head;
+    let root = build_balanced(link_ops, &mut head, len, 0, red_depth);
+    if let Some(root) = root {
+        link_ops.set_parent(root, None);
+        link_ops.set_color(root, Color::Black);
+    }
+    root
+}
+
+// Returns the black height of a tree, counting the root and not counting the
+// null leaves.
+#[inline]
+unsafe fn black_height<T: RBTreeOps>(link_ops: &T, root: Option<T::LinkPtr>) -> usize {
+    let mut height = 0;
+    let mut x = root;
+    while let Some(y) = x {
+        if link_ops.color(y) == Color::Black {
+            height += 1;
+        }
+        x = link_ops.left(y);
+    }
+    height
+}
+
+// Records that the links of the tree rooted at `root` now belong to
+// `link_ops`, after they were moved in from another tree.
+#[inline]
+unsafe fn adopt<T: RBTreeOps>(link_ops: &mut T, root: T::LinkPtr) {
+    if T::TRACKS_OWNER {
+        let mut x = Some(first_child(link_ops, root));
+        while let Some(y) = x {
+            link_ops.adopt_link(y);
+            x = next(link_ops, y);
+        }
+    }
+}
+
+// Detaches a subtree from its parent and turns it into a valid red-black tree
+// by making its root black. Returns the root and the new black height.
+#[inline]
+unsafe fn detach_subtree<T: RBTreeOps>(
+    link_ops: &mut T,
+    ptr: Option<T::LinkPtr>,
+    height: usize,
+) -> (Option<T::LinkPtr>, usize) {
+    if let Some(ptr) = ptr {
+        link_ops.set_parent(ptr, None);
+        if link_ops.color(ptr) == Color::Red {
+            link_ops.set_color(ptr, Color::Black);
+            return (Some(ptr), height + 1);
+        }
+    }
+    (ptr, height)
+}
+
+// Joins two trees with black roots and the given black heights, using `mid` as
+// the element between them. All elements of `left` must be ordered before
+// `mid`, and all elements of `right` after it.
+//
+// This runs in O(|left_height - right_height| + 1) time and returns the root
+// and black height of the resulting tree.
+unsafe fn join<T: RBTreeOps>(
+    link_ops: &mut T,
+    left: Option<T::LinkPtr>,
+    left_height: usize,
+    mid: T::LinkPtr,
+    right: Option<T::LinkPtr>,
+    right_height: usize,
+) -> (Option<T::LinkPtr>, usize) {
+    if left_height == right_height {
+        link_ops.set_parent(mid, None);
+        link_ops.set_color(mid, Color::Black);
+        link_ops.set_left(mid, left);
+        link_ops.set_right(mid, right);
+        if let Some(left) = left {
+            link_ops.set_parent(left, Some(mid));
+        }
+        if let Some(right) = right {
+            link_ops.set_parent(right, Some(mid));
+        }
+        return (Some(mid), left_height + 1);
+    }
+
+    // Walk down the inner spine of the taller tree until we find a black node
+    // (or null leaf) with the same black height as the shorter tree. That
+    // subtree and the shorter tree become the children of `mid`, which is
+    // inserted as a red node in place of the subtree.
+    let taller_left = left_height > right_height;
+    let (mut root, target_height) = if taller_left {
+        (left, right_height)
+    } else {
+        (right, left_height)
+    };
+    let mut height = left_height.max(right_height);
+    let mut parent = None;
+    let mut x = root;
+    while let Some(y) = x {
+        if link_ops.color(y) == Color::Black {
+            if height == target_height {
+                break;
+            }
+            height -= 1;
+        }
+        parent = Some(y);
+        x = if taller_left {
+            link_ops.right(y)
+        } else {
+            link_ops.left(y)
+        };
+    }
+    let parent = parent.unwrap_unchecked();
+
+    let (new_left, new_right) = if taller_left { (x, right) } else { (left, x) };
+    link_ops.set_left(mid, new_left);
+    link_ops.set_right(mid, new_right);
+    if let Some(new_left) = new_left {
+        link_ops.set_parent(new_left, Some(mid));
+    }
+    if let Some(new_right) = new_right {
+        link_ops.set_parent(new_right, Some(mid));
+    }
+    link_ops.set_parent(mid, Some(parent));
+    link_ops.set_color(mid, Color::Red);
+    if taller_left {
+        link_ops.set_right(parent, Some(mid));
+    } else {
+        link_ops.set_left(parent, Some(mid));
+    }
+
+    let grew = post_insert(link_ops, mid, &mut root);
+    (root, left_height.max(right_height) + grew as usize)
+}
+
+// Splits the subtree rooted at `ptr` with the given black height into two
+// trees. Elements for which `goes_right` returns `true` must form a suffix of
+// the subtree; they are moved into the right tree and all other elements into
+// the left tree. The roots and black heights of both trees are returned.
+//
+// `goes_right` is only called on the nodes along a single path from the root,
+// so this runs in O(log n) time.
+unsafe fn split<A, T, F>(
+    adapter: &mut A,
+    ptr: Option<T::LinkPtr>,
+    height: usize,
+    goes_right: &mut F,
+) -> [(Option<T::LinkPtr>, usize); 2]
+where
+    A: Adapter<LinkOps = T>,
+    T: RBTreeOps,
+    F: FnMut(&A, T::LinkPtr) -> bool,
+{
+    let ptr = match ptr {
+        Some(ptr) => ptr,
+        None => return [(None, 0), (None, 0)],
+    };
+    let link_ops = adapter.link_ops();
+    let child_height = if link_ops.color(ptr) == Color::Black {
+        height - 1
+    } else {
+        height
+    };
+    let left = link_ops.left(ptr);
+    let right = link_ops.right(ptr);
+
+    if goes_right(adapter, ptr) {
+        let [lower, (middle, middle_height)] = split(adapter, left, child_height, goes_right);
+        let link_ops = adapter.link_ops_mut();
+        let (right, right_height) = detach_subtree(link_ops, right, child_height);
+        let upper = join(link_ops, middle, middle_height, ptr, right, right_height);
+        [lower, upper]
+    } else {
+        let [(middle, middle_height), upper] = split(adapter, right, child_height, goes_right);
+        let link_ops = adapter.link_ops_mut();
+        let (left, left_height) = detach_subtree(link_ops, left, child_height);
+        let lower = join(link_ops, left, left_height, ptr, middle, middle_height);
+        [lower, upper]
+    }
+}
+
+// Threads all elements of two trees into a single chain through their right
+// pointers, in ascending order according to `less`, and returns the head of
+// the chain along with the number of elements in it. Elements of `a` are
+// placed before elements of `b` unless `less(b, a)` returns `true`.
+unsafe fn merge_into_chain<A, T, F>(
+    adapter: &mut A,
+    a: Option<T::LinkPtr>,
+    b: Option<T::LinkPtr>,
+    less: &mut F,
+) -> (Option<T::LinkPtr>, usize)
+where
+    A: Adapter<LinkOps = T>,
+    T: RBTreeOps,
+    F: FnMut(&A, T::LinkPtr, T::LinkPtr) -> bool,
+{
+    // Walking to the next node of a tree only reads the right pointer of the
+    // current node and the left and parent pointers of its ancestors, so it is
+    // safe to overwrite the right pointers of nodes that were already visited.
+    let mut a = a.map(|root| first_child(adapter.link_ops(), root));
+    let mut b = b.map(|root| first_child(adapter.link_ops(), root));
+    let mut head = None;
+    let mut tail = None;
+    let mut len = 0;
+    loop {
+        let node = match (a, b) {
+            (Some(x), Some(y)) => {
+                if less(adapter, y, x) {
+                    b = next(adapter.link_ops(), y);
+                    y
+                } else {
+                    a = next(adapter.link_ops(), x);
+                    x
+                }
+            }
+            (Some(x), None) => {
+                a = next(adapter.link_ops(), x);
+                x
+            }
+            (None, Some(y)) => {
+                b = next(adapter.link_ops(), y);
+                y
+            }
+            (None, None) => break,
+        };
+        if let Some(tail) = tail {
+            adapter.link_ops_mut().set_right(tail, Some(node));
+        } else {
+            head = Some(node);
+        }
+        tail = Some(node);
+        len += 1;
+    }
+    if let Some(tail) = tail {
+        adapter.link_ops_mut().set_right(tail, None);
+    }
+    (head, len)
+}
+
 // =============================================================================
 // Cursor, CursorMut
 // =============================================================================
@@ -868,6 +1176,17 @@ where
     }
 }
 
+// Allow a cursor to pick the element to remove or modify in a `MultiIndex`.
+impl<'a, A: Adapter> From<Cursor<'a, A>> for Position<'a, <A::PointerOps as PointerOps>::Value>
+where
+    A::LinkOps: RBTreeOps,
+{
+    #[inline]
+    fn from(cursor: Cursor<'a, A>) -> Self {
+        unsafe { Position::new(cursor.tree as *const RBTree<A> as *const (), cursor.get()) }
+    }
+}
+
 /// A cursor which provides mutable access to a `RBTree`.
 pub struct CursorMut<'a, A: Adapter>
 where
@@ -997,6 +1316,22 @@ where
         }
     }
 
+    /// Removes the current element from the `RBTree` and passes it to
+    /// `dispose`.
+    ///
+    /// This behaves like `remove`, except that the removed element is handed
+    /// to `dispose` instead of being returned, and the cursor is moved to point
+    /// to the next element.
+    ///
+    /// Returns `true` if an element was removed.
+    #[inline]
+    pub fn remove_and_dispose<F>(&mut self, dispose: F) -> bool
+    where
+        F: FnOnce(<A::PointerOps as PointerOps>::Pointer),
+    {
+        self.remove().map(dispose).is_some()
+    }
+
     /// Removes the current element from the `RBTree` and inserts another
     /// object in its place.
     ///
@@ -1103,32 +1438,71 @@ where
     pub fn insert_before(&mut self, val: <A::PointerOps as PointerOps>::Pointer) {
         unsafe {
             let new = self.tree.node_from_value(val);
-            let link_ops = self.tree.adapter.link_ops_mut();
+            self.insert_node_before(new);
+        }
+    }
 
-            if let Some(root) = self.tree.root {
-                if let Some(current) = self.current {
-                    if link_ops.left(current).is_some() {
-                        let prev = prev(link_ops, current).unwrap_unchecked();
-                        insert_right(link_ops, prev, new, &mut self.tree.root);
-                    } else {
-                        insert_left(link_ops, current, new, &mut self.tree.root);
-                    }
+    #[inline]
+    unsafe fn insert_node_before(&mut self, new: <A::LinkOps as link_ops::LinkOps>::LinkPtr) {
+        let link_ops = self.tree.adapter.link_ops_mut();
+
+        if let Some(root) = self.tree.root {
+            if let Some(current) = self.current {
+                if link_ops.left(current).is_some() {
+                    let prev = prev(link_ops, current).unwrap_unchecked();
+                    insert_right(link_ops, prev, new, &mut self.tree.root);
                 } else {
-                    insert_right(
-                        link_ops,
-                        last_child(link_ops, root),
-                        new,
-                        &mut self.tree.root,
-                    );
+                    insert_left(link_ops, current, new, &mut self.tree.root);
                 }
             } else {
-                self.tree.insert_root(new);
+                insert_right(
+                    link_ops,
+                    last_child(link_ops, root),
+                    new,
+                    &mut self.tree.root,
+                );
             }
+        } else {
+            self.tree.insert_root(new);
         }
     }
 }
 
-impl<'a, A: for<'b> KeyAdapter<'b>> CursorMut<'a, A>
+impl<'a, A: Adapter> Cursor<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+    A::PointerOps: PinnedPointerOps,
+{
+    /// Returns a pinned reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This is only available for collections of pinned pointers such as
+    /// `Pin<Box<T>>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_pin(&self) -> Option<Pin<&'a <A::PointerOps as PointerOps>::Value>> {
+        self.get().map(|value| unsafe { Pin::new_unchecked(value) })
+    }
+}
+
+impl<'a, A: Adapter> CursorMut<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+    A::PointerOps: PinnedPointerOps,
+{
+    /// Returns a pinned reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This is only available for collections of pinned pointers such as
+    /// `Pin<Box<T>>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_pin(&self) -> Option<Pin<&<A::PointerOps as PointerOps>::Value>> {
+        self.get().map(|value| unsafe { Pin::new_unchecked(value) })
+    }
+}
+
+impl<'a, A: for<'b> ComparatorAdapter<'b>> CursorMut<'a, A>
 where
     <A as Adapter>::LinkOps: RBTreeOps,
 {
@@ -1142,14 +1516,117 @@ where
     /// Panics if the new element is already linked to a different intrusive
     /// collection.
     #[inline]
-    pub fn insert<'c>(&'c mut self, val: <A::PointerOps as PointerOps>::Pointer)
-    where
-        <A as KeyAdapter<'c>>::Key: Ord,
-    {
+    pub fn insert(&mut self, val: <A::PointerOps as PointerOps>::Pointer) {
         // We explicitly drop the returned CursorMut here, otherwise we would
         // end up with multiple CursorMut in the same collection.
         self.tree.insert(val);
     }
+
+    /// Inserts a new element into the `RBTree`, using the current position of
+    /// the cursor as a hint.
+    ///
+    /// If the key of the new element lies between the keys of the previous
+    /// element and the current one, the new element is inserted directly
+    /// before the current element without searching the tree. Otherwise this
+    /// falls back to a normal insertion at the correct position based on the
+    /// key. If the cursor is pointing to the null object then the hint is the
+    /// end of the tree.
+    ///
+    /// The cursor is not moved, which makes it possible to insert elements in
+    /// ascending order with only two key comparisons per element by
+    /// repeatedly inserting through a null cursor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new element is already linked to a different intrusive
+    /// collection.
+    #[inline]
+    pub fn insert_with_hint(&mut self, val: <A::PointerOps as PointerOps>::Pointer) {
+        unsafe {
+            let new = self.tree.node_from_value(val);
+            let prev = match self.current {
+                Some(current) => prev(self.tree.adapter.link_ops(), current),
+                None => self
+                    .tree
+                    .root
+                    .map(|root| last_child(self.tree.adapter.link_ops(), root)),
+            };
+            let after_prev = match prev {
+                Some(prev) => self.tree.compare_nodes(prev, new) != Ordering::Greater,
+                None => true,
+            };
+            let before_current = match self.current {
+                Some(current) => self.tree.compare_nodes(new, current) != Ordering::Greater,
+                None => true,
+            };
+            if after_prev && before_current {
+                self.insert_node_before(new);
+            } else {
+                self.tree.insert_node(new);
+            }
+        }
+    }
+
+    /// Runs `f` on the current element, which may change its key, and then
+    /// moves the element to its correct position in the tree.
+    ///
+    /// If the element still lies between its neighbours after `f` returns, it
+    /// is left in place without searching the tree. Otherwise it is unlinked
+    /// and reinserted based on its new key, after any elements with an equal
+    /// key. The cursor keeps pointing to the element either way.
+    ///
+    /// If the cursor is pointing to the null object then `f` is not called.
+    #[inline]
+    pub fn update_key<F>(&mut self, f: F)
+    where
+        F: FnOnce(&<A::PointerOps as PointerOps>::Value),
+    {
+        if let Some(current) = self.current {
+            unsafe {
+                f(&*self.tree.adapter.get_value(current));
+                self.reposition(current);
+            }
+        }
+    }
+
+    /// Runs `f` on a mutable reference to the data of the current element,
+    /// and then moves the element to its correct position in the tree in the
+    /// same way as `update_key`, since the data may include the key.
+    ///
+    /// This is only available for collections of uniquely owned pointers such
+    /// as `Box<T>`. If the cursor is pointing to the null object then `f` is
+    /// not called.
+    #[inline]
+    pub fn update_data<F>(&mut self, f: F)
+    where
+        A::PointerOps: UniquePointerOps,
+        <A::PointerOps as PointerOps>::Value: DataMut,
+        F: FnOnce(&mut <<A::PointerOps as PointerOps>::Value as DataMut>::Data),
+    {
+        if let Some(current) = self.current {
+            unsafe {
+                let value = self.tree.adapter.get_value(current);
+                f((*(value as *mut <A::PointerOps as PointerOps>::Value)).data_mut());
+                self.reposition(current);
+            }
+        }
+    }
+
+    // Moves `current`, whose key may have changed, to its correct position.
+    #[inline]
+    unsafe fn reposition(&mut self, current: <A::LinkOps as link_ops::LinkOps>::LinkPtr) {
+        use link_ops::LinkOps;
+
+        let link_ops = self.tree.adapter.link_ops();
+        let prev = prev(link_ops, current);
+        let next = next(link_ops, current);
+        if !self.tree.is_between(prev, current, next) {
+            let link_ops = self.tree.adapter.link_ops_mut();
+            remove(link_ops, current, &mut self.tree.root);
+            link_ops.acquire_link(current);
+            self.tree.insert_node(current);
+        }
+    }
 }
 
 // =============================================================================
@@ -1163,10 +1640,13 @@ where
 ///
 /// Note that you are responsible for ensuring that the elements in a `RBTree`
 /// remain in ascending key order. This property can be violated, either because
-/// the key of an element was modified, or because the
-/// `insert_before`/`insert_after` methods of `CursorMut` were incorrectly used.
-/// If this situation occurs, memory safety will not be violated but the `find`,
-/// `upper_bound`, `lower_bound` and `range` may return incorrect results.
+/// the key of an element was modified without using `CursorMut::update_key` or
+/// `CursorMut::update_data`, or because the `insert_before`/`insert_after`
+/// methods of `CursorMut` were incorrectly used. If this situation occurs,
+/// memory safety will not be
+/// violated but the `find`, `upper_bound`, `lower_bound` and `range` may return
+/// incorrect results. In debug builds `find` panics if it notices that keys are
+/// out of order.
 pub struct RBTree<A: Adapter>
 where
     A::LinkOps: RBTreeOps,
@@ -1221,6 +1701,50 @@ where
         }
     }
 
+    /// Creates a `RBTree` from an iterator of elements which are already in
+    /// ascending key order.
+    ///
+    /// The tree is built in O(n) time without comparing any keys, which makes
+    /// this much faster than inserting the elements one by one.
+    ///
+    /// You must ensure that the elements are yielded in increasing order.
+    /// Failure to do this may lead to `find`, `upper_bound`, `lower_bound` and
+    /// `range` returning incorrect results.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the elements is already linked to a different
+    /// intrusive collection.
+    pub fn from_sorted_iter<I>(adapter: A, iter: I) -> RBTree<A>
+    where
+        I: IntoIterator<Item = <A::PointerOps as PointerOps>::Pointer>,
+    {
+        let mut tree = RBTree::new(adapter);
+        // Thread the elements into a chain through their right pointers first
+        // so that we know how many there are. If the iterator or
+        // `node_from_value` panics, the elements collected so far are leaked
+        // but this is harmless since there is nothing you can do with just a
+        // Link.
+        let mut head = None;
+        let mut tail: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr> = None;
+        let mut len = 0;
+        for val in iter {
+            let new = tree.node_from_value(val);
+            unsafe {
+                tree.adapter.link_ops_mut().set_right(new, None);
+                if let Some(tail) = tail {
+                    tree.adapter.link_ops_mut().set_right(tail, Some(new));
+                } else {
+                    head = Some(new);
+                }
+            }
+            tail = Some(new);
+            len += 1;
+        }
+        tree.root = unsafe { build_from_chain(tree.adapter.link_ops_mut(), head, len) };
+        tree
+    }
+
     /// Returns `true` if the `RBTree` is empty.
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -1314,64 +1838,209 @@ where
     }
 
     #[inline]
-    unsafe fn insert_root(&mut self, node: <A::LinkOps as link_ops::LinkOps>::LinkPtr) {
-        self.adapter.link_ops_mut().set_parent(node, None);
-        self.adapter.link_ops_mut().set_color(node, Color::Black);
-        self.adapter.link_ops_mut().set_left(node, None);
-        self.adapter.link_ops_mut().set_right(node, None);
-        self.root = Some(node);
-    }
-
-    /// Gets an iterator over the objects in the `RBTree`.
-    #[inline]
-    pub fn iter(&self) -> Iter<'_, A> {
+    fn partition_point_internal<F>(
+        &self,
+        mut pred: F,
+    ) -> Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>
+    where
+        F: FnMut(&<A::PointerOps as PointerOps>::Value) -> bool,
+    {
         let link_ops = self.adapter.link_ops();
 
-        if let Some(root) = self.root {
-            Iter {
-                head: Some(unsafe { first_child(link_ops, root) }),
-                tail: Some(unsafe { last_child(link_ops, root) }),
-                tree: self,
-            }
-        } else {
-            Iter {
-                head: None,
-                tail: None,
-                tree: self,
+        let mut tree = self.root;
+        let mut result = None;
+        while let Some(x) = tree {
+            if pred(unsafe { &*self.adapter.get_value(x) }) {
+                tree = unsafe { link_ops.right(x) };
+            } else {
+                result = tree;
+                tree = unsafe { link_ops.left(x) };
             }
         }
+        result
     }
 
+    /// Returns a `Cursor` pointing to the first element for which the given
+    /// predicate returns false. If the predicate returns true for all elements
+    /// then a null cursor is returned.
+    ///
+    /// The tree must be partitioned according to the predicate, meaning that
+    /// all elements for which it returns true come before all elements for
+    /// which it returns false. If this is not the case then the returned
+    /// element is unspecified, but this will not cause any memory unsafety.
+    ///
+    /// This only visits O(log n) elements and does not require the adapter to
+    /// implement `ComparatorAdapter`.
     #[inline]
-    fn clear_recurse(&mut self, current: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>) {
-        use link_ops::LinkOps;
-        // If adapter.get_value or Pointer::from_raw panic here, it will leak
-        // the nodes and keep them linked. However this is harmless since there
-        // is nothing you can do with just a Link.
-        if let Some(current) = current {
-            unsafe {
-                let left = self.adapter.link_ops_mut().left(current);
-                let right = self.adapter.link_ops_mut().right(current);
-                self.clear_recurse(left);
-                self.clear_recurse(right);
-                self.adapter.link_ops_mut().release_link(current);
-                self.adapter
-                    .pointer_ops()
-                    .from_raw(self.adapter.get_value(current));
-            }
+    pub fn partition_point<F>(&self, pred: F) -> Cursor<'_, A>
+    where
+        F: FnMut(&<A::PointerOps as PointerOps>::Value) -> bool,
+    {
+        Cursor {
+            current: self.partition_point_internal(pred),
+            tree: self,
         }
     }
 
-    /// Removes all elements from the `RBTree`.
+    /// Returns a `CursorMut` pointing to the first element for which the given
+    /// predicate returns false. If the predicate returns true for all elements
+    /// then a null cursor is returned.
     ///
-    /// This will unlink all object currently in the tree, which requires
-    /// iterating through all elements in the `RBTree`. Each element is
-    /// converted back to an owned pointer and then dropped.
+    /// See `partition_point` for the requirements on the predicate.
     #[inline]
-    pub fn clear(&mut self) {
-        let root = self.root.take();
-        self.clear_recurse(root);
-    }
+    pub fn partition_point_mut<F>(&mut self, pred: F) -> CursorMut<'_, A>
+    where
+        F: FnMut(&<A::PointerOps as PointerOps>::Value) -> bool,
+    {
+        CursorMut {
+            current: self.partition_point_internal(pred),
+            tree: self,
+        }
+    }
+
+    #[inline]
+    fn find_by_internal<F>(&self, mut f: F) -> Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>
+    where
+        F: FnMut(&<A::PointerOps as PointerOps>::Value) -> Ordering,
+    {
+        let link_ops = self.adapter.link_ops();
+
+        let mut tree = self.root;
+        while let Some(x) = tree {
+            match f(unsafe { &*self.adapter.get_value(x) }) {
+                Ordering::Less => tree = unsafe { link_ops.right(x) },
+                Ordering::Equal => return tree,
+                Ordering::Greater => tree = unsafe { link_ops.left(x) },
+            }
+        }
+        None
+    }
+
+    /// Returns a `Cursor` pointing to an element for which the given function
+    /// returns `Ordering::Equal`. If no such element is found then a null
+    /// cursor is returned.
+    ///
+    /// As with `slice::binary_search_by`, the function should return whether
+    /// an element is `Less`, `Equal` or `Greater` than the desired target, and
+    /// the results must be consistent with the order of the elements in the
+    /// tree. If multiple elements match then an arbitrary one is returned.
+    ///
+    /// This only visits O(log n) elements and does not require the adapter to
+    /// implement `ComparatorAdapter`.
+    #[inline]
+    pub fn find_by<F>(&self, f: F) -> Cursor<'_, A>
+    where
+        F: FnMut(&<A::PointerOps as PointerOps>::Value) -> Ordering,
+    {
+        Cursor {
+            current: self.find_by_internal(f),
+            tree: self,
+        }
+    }
+
+    /// Returns a `CursorMut` pointing to an element for which the given
+    /// function returns `Ordering::Equal`. If no such element is found then a
+    /// null cursor is returned.
+    ///
+    /// See `find_by` for the requirements on the function.
+    #[inline]
+    pub fn find_by_mut<F>(&mut self, f: F) -> CursorMut<'_, A>
+    where
+        F: FnMut(&<A::PointerOps as PointerOps>::Value) -> Ordering,
+    {
+        CursorMut {
+            current: self.find_by_internal(f),
+            tree: self,
+        }
+    }
+
+    #[inline]
+    unsafe fn insert_root(&mut self, node: <A::LinkOps as link_ops::LinkOps>::LinkPtr) {
+        self.adapter.link_ops_mut().set_parent(node, None);
+        self.adapter.link_ops_mut().set_color(node, Color::Black);
+        self.adapter.link_ops_mut().set_left(node, None);
+        self.adapter.link_ops_mut().set_right(node, None);
+        self.root = Some(node);
+    }
+
+    /// Gets an iterator over the objects in the `RBTree`.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, A> {
+        let link_ops = self.adapter.link_ops();
+
+        if let Some(root) = self.root {
+            Iter {
+                head: Some(unsafe { first_child(link_ops, root) }),
+                tail: Some(unsafe { last_child(link_ops, root) }),
+                tree: self,
+            }
+        } else {
+            Iter {
+                head: None,
+                tail: None,
+                tree: self,
+            }
+        }
+    }
+
+    #[inline]
+    fn clear_recurse<F>(
+        &mut self,
+        current: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        dispose: &mut F,
+    ) where
+        F: FnMut(<A::PointerOps as PointerOps>::Pointer),
+    {
+        use link_ops::LinkOps;
+        // If adapter.get_value, Pointer::from_raw or dispose panic here, it
+        // will leak the nodes and keep them linked. However this is harmless
+        // since there is nothing you can do with just a Link, and the owner of
+        // the tree has already been renewed.
+        if let Some(current) = current {
+            unsafe {
+                let left = self.adapter.link_ops_mut().left(current);
+                let right = self.adapter.link_ops_mut().right(current);
+                self.clear_recurse(left, dispose);
+                self.clear_recurse(right, dispose);
+                self.adapter.link_ops_mut().release_link(current);
+                dispose(
+                    self.adapter
+                        .pointer_ops()
+                        .from_raw(self.adapter.get_value(current)),
+                );
+            }
+        }
+    }
+
+    /// Removes all elements from the `RBTree`.
+    ///
+    /// This will unlink all object currently in the tree, which requires
+    /// iterating through all elements in the `RBTree`. Each element is
+    /// converted back to an owned pointer and then dropped.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.clear_and_dispose(drop);
+    }
+
+    /// Removes all elements from the `RBTree`, passing each one to `dispose`.
+    ///
+    /// This behaves like `clear`, except that each element is converted back
+    /// to an owned pointer and handed to `dispose` instead of being dropped.
+    /// This can be used to return elements to a pool or to run some custom
+    /// teardown on them.
+    #[inline]
+    pub fn clear_and_dispose<F>(&mut self, mut dispose: F)
+    where
+        F: FnMut(<A::PointerOps as PointerOps>::Pointer),
+    {
+        use link_ops::LinkOps;
+
+        let root = self.root.take();
+        // Renew the owner first, so that elements which are left linked if
+        // `dispose` panics don't appear to belong to this collection.
+        self.adapter.link_ops_mut().renew_owner();
+        self.clear_recurse(root, &mut dispose);
+    }
 
     /// Empties the `RBTree` without unlinking or freeing objects in it.
     ///
@@ -1381,7 +2050,60 @@ where
     /// `force_unlink` function on them.
     #[inline]
     pub fn fast_clear(&mut self) {
+        use link_ops::LinkOps;
+
         self.root = None;
+        self.adapter.link_ops_mut().renew_owner();
+    }
+
+    /// Removes the first element of the tree and returns a pointer to it. If
+    /// the tree is empty then `None` is returned.
+    #[inline]
+    pub fn pop_first(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        self.front_mut().remove()
+    }
+
+    /// Removes the first element of the tree and passes it to `dispose`.
+    ///
+    /// Returns `true` if an element was removed, or `false` if the tree was
+    /// empty.
+    #[inline]
+    pub fn pop_first_and_dispose<F>(&mut self, dispose: F) -> bool
+    where
+        F: FnOnce(<A::PointerOps as PointerOps>::Pointer),
+    {
+        self.pop_first().map(dispose).is_some()
+    }
+
+    /// Removes the last element of the tree and returns a pointer to it. If
+    /// the tree is empty then `None` is returned.
+    #[inline]
+    pub fn pop_last(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        self.back_mut().remove()
+    }
+
+    /// Returns an `OccupiedEntry` for the first element of the tree. If the
+    /// tree is empty then `None` is returned.
+    #[inline]
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, A>> {
+        let cursor = self.front_mut();
+        if cursor.is_null() {
+            None
+        } else {
+            Some(OccupiedEntry { cursor })
+        }
+    }
+
+    /// Returns an `OccupiedEntry` for the last element of the tree. If the
+    /// tree is empty then `None` is returned.
+    #[inline]
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, A>> {
+        let cursor = self.back_mut();
+        if cursor.is_null() {
+            None
+        } else {
+            Some(OccupiedEntry { cursor })
+        }
     }
 
     /// Takes all the elements out of the `RBTree`, leaving it empty. The
@@ -1391,37 +2113,110 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         let tree = RBTree {
             root: self.root,
             adapter: self.adapter.clone(),
         };
         self.root = None;
+        self.adapter.link_ops_mut().renew_owner();
         tree
     }
 }
 
-impl<A: for<'a> KeyAdapter<'a>> RBTree<A>
+impl<A: for<'a> ComparatorAdapter<'a>> RBTree<A>
 where
     <A as Adapter>::LinkOps: RBTreeOps,
 {
+    // Compares the keys of two elements using the comparator of the adapter.
+    #[inline]
+    unsafe fn compare_nodes<'a>(
+        &self,
+        a: <A::LinkOps as link_ops::LinkOps>::LinkPtr,
+        b: <A::LinkOps as link_ops::LinkOps>::LinkPtr,
+    ) -> Ordering
+    where
+        <A::PointerOps as PointerOps>::Value: 'a,
+    {
+        let a = self.adapter.get_sort_key(&*self.adapter.get_value(a));
+        let b = self.adapter.get_sort_key(&*self.adapter.get_value(b));
+        self.adapter.comparator().compare(&a, &b)
+    }
+
+    // Compares a key with the key of an element using the comparator of the
+    // adapter.
     #[inline]
-    fn find_internal<'a, Q: ?Sized + Ord>(
+    unsafe fn compare_key<'a, Q: ?Sized>(
+        &self,
+        key: &Q,
+        ptr: <A::LinkOps as link_ops::LinkOps>::LinkPtr,
+    ) -> Ordering
+    where
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+        <A::PointerOps as PointerOps>::Value: 'a,
+    {
+        let current = self.adapter.get_sort_key(&*self.adapter.get_value(ptr));
+        self.adapter.comparator().compare_with(key, &current)
+    }
+
+    // Checks that `x` is not ordered before `lower` or after `upper`.
+    #[inline]
+    unsafe fn is_between<'a>(
+        &self,
+        lower: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        x: <A::LinkOps as link_ops::LinkOps>::LinkPtr,
+        upper: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+    ) -> bool
+    where
+        <A::PointerOps as PointerOps>::Value: 'a,
+    {
+        let after_lower = match lower {
+            Some(lower) => self.compare_nodes(lower, x) != Ordering::Greater,
+            None => true,
+        };
+        let before_upper = match upper {
+            Some(upper) => self.compare_nodes(x, upper) != Ordering::Greater,
+            None => true,
+        };
+        after_lower && before_upper
+    }
+
+    #[inline]
+    fn find_internal<'a, Q: ?Sized>(
         &self,
         key: &Q,
     ) -> Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
         <A::PointerOps as PointerOps>::Value: 'a,
     {
         let link_ops = self.adapter.link_ops();
 
+        // The nodes at which the search last went right and left. In debug
+        // builds every node visited is checked to lie between them, which
+        // catches keys that were modified without using `update_key`.
+        let mut lower = None;
+        let mut upper = None;
+
         let mut tree = self.root;
         while let Some(x) = tree {
-            let current = unsafe { &*self.adapter.get_value(x) };
-            match key.cmp(self.adapter.get_key(current).borrow()) {
-                Ordering::Less => tree = unsafe { link_ops.left(x) },
+            debug_assert!(
+                unsafe { self.is_between(lower, x, upper) },
+                "RBTree keys are out of order, was a key modified without `update_key`?"
+            );
+            match unsafe { self.compare_key(key, x) } {
+                Ordering::Less => {
+                    upper = tree;
+                    tree = unsafe { link_ops.left(x) };
+                }
                 Ordering::Equal => return tree,
-                Ordering::Greater => tree = unsafe { link_ops.right(x) },
+                Ordering::Greater => {
+                    lower = tree;
+                    tree = unsafe { link_ops.right(x) };
+                }
             }
         }
         None
@@ -1433,9 +2228,10 @@ where
     /// If multiple elements with an identical key are found then an arbitrary
     /// one is returned.
     #[inline]
-    pub fn find<'a, Q: ?Sized + Ord>(&'a self, key: &Q) -> Cursor<'a, A>
+    pub fn find<'a, Q: ?Sized>(&'a self, key: &Q) -> Cursor<'a, A>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
     {
         Cursor {
             current: self.find_internal(key),
@@ -1449,9 +2245,10 @@ where
     /// If multiple elements with an identical key are found then an arbitrary
     /// one is returned.
     #[inline]
-    pub fn find_mut<'a, Q: ?Sized + Ord>(&'a mut self, key: &Q) -> CursorMut<'a, A>
+    pub fn find_mut<'a, Q: ?Sized>(&'a mut self, key: &Q) -> CursorMut<'a, A>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
     {
         CursorMut {
             current: self.find_internal(key),
@@ -1459,13 +2256,55 @@ where
         }
     }
 
+    /// Returns a reference to an element with the given key. If no such
+    /// element is found then `None` is returned.
+    ///
+    /// If multiple elements with an identical key are found then an arbitrary
+    /// one is returned.
     #[inline]
-    fn lower_bound_internal<'a, Q: ?Sized + Ord>(
+    pub fn get<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<&'a <A::PointerOps as PointerOps>::Value>
+    where
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        self.find(key).get()
+    }
+
+    /// Returns `true` if the tree contains an element with the given key.
+    #[inline]
+    pub fn contains_key<'a, Q: ?Sized>(&'a self, key: &Q) -> bool
+    where
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        self.find_internal(key).is_some()
+    }
+
+    /// Removes an element with the given key from the tree and returns a
+    /// pointer to it. If no such element is found then `None` is returned.
+    ///
+    /// If multiple elements with an identical key are found then an arbitrary
+    /// one is removed.
+    #[inline]
+    pub fn remove<'a, Q: ?Sized>(
+        &'a mut self,
+        key: &Q,
+    ) -> Option<<A::PointerOps as PointerOps>::Pointer>
+    where
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        self.find_mut(key).remove()
+    }
+
+    #[inline]
+    fn lower_bound_internal<'a, Q: ?Sized>(
         &self,
         bound: Bound<&Q>,
     ) -> Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
         <A::PointerOps as PointerOps>::Value: 'a,
     {
         let link_ops = self.adapter.link_ops();
@@ -1473,11 +2312,10 @@ where
         let mut tree = self.root;
         let mut result = None;
         while let Some(x) = tree {
-            let current = unsafe { &*self.adapter.get_value(x) };
             let cond = match bound {
                 Unbounded => true,
-                Included(key) => key <= self.adapter.get_key(current).borrow(),
-                Excluded(key) => key < self.adapter.get_key(current).borrow(),
+                Included(key) => unsafe { self.compare_key(key, x) != Ordering::Greater },
+                Excluded(key) => unsafe { self.compare_key(key, x) == Ordering::Less },
             };
             if cond {
                 result = tree;
@@ -1492,13 +2330,18 @@ where
     /// Returns a `Cursor` pointing to the lowest element whose key is above
     /// the given bound. If no such element is found then a null cursor is
     /// returned.
+    ///
+    /// The bound may be given either as a crate `Bound` or as a
+    /// `core::ops::Bound`.
     #[inline]
-    pub fn lower_bound<'a, Q: ?Sized + Ord>(&'a self, bound: Bound<&Q>) -> Cursor<'a, A>
+    pub fn lower_bound<'a, 'b, Q: ?Sized + 'b, B>(&'a self, bound: B) -> Cursor<'a, A>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        B: Into<Bound<&'b Q>>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
     {
         Cursor {
-            current: self.lower_bound_internal(bound),
+            current: self.lower_bound_internal(bound.into()),
             tree: self,
         }
     }
@@ -1507,23 +2350,26 @@ where
     /// above the given bound. If no such element is found then a null
     /// cursor is returned.
     #[inline]
-    pub fn lower_bound_mut<'a, Q: ?Sized + Ord>(&'a mut self, bound: Bound<&Q>) -> CursorMut<'a, A>
+    pub fn lower_bound_mut<'a, 'b, Q: ?Sized + 'b, B>(&'a mut self, bound: B) -> CursorMut<'a, A>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        B: Into<Bound<&'b Q>>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
     {
         CursorMut {
-            current: self.lower_bound_internal(bound),
+            current: self.lower_bound_internal(bound.into()),
             tree: self,
         }
     }
 
     #[inline]
-    fn upper_bound_internal<'a, Q: ?Sized + Ord>(
+    fn upper_bound_internal<'a, Q: ?Sized>(
         &self,
         bound: Bound<&Q>,
     ) -> Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
         <A::PointerOps as PointerOps>::Value: 'a,
     {
         let link_ops = self.adapter.link_ops();
@@ -1531,11 +2377,10 @@ where
         let mut tree = self.root;
         let mut result = None;
         while let Some(x) = tree {
-            let current = unsafe { &*self.adapter.get_value(x) };
             let cond = match bound {
                 Unbounded => false,
-                Included(key) => key < self.adapter.get_key(current).borrow(),
-                Excluded(key) => key <= self.adapter.get_key(current).borrow(),
+                Included(key) => unsafe { self.compare_key(key, x) == Ordering::Less },
+                Excluded(key) => unsafe { self.compare_key(key, x) != Ordering::Greater },
             };
             if cond {
                 tree = unsafe { link_ops.left(x) };
@@ -1550,13 +2395,18 @@ where
     /// Returns a `Cursor` pointing to the last element whose key is below
     /// the given bound. If no such element is found then a null cursor is
     /// returned.
+    ///
+    /// The bound may be given either as a crate `Bound` or as a
+    /// `core::ops::Bound`.
     #[inline]
-    pub fn upper_bound<'a, Q: ?Sized + Ord>(&'a self, bound: Bound<&Q>) -> Cursor<'a, A>
+    pub fn upper_bound<'a, 'b, Q: ?Sized + 'b, B>(&'a self, bound: B) -> Cursor<'a, A>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        B: Into<Bound<&'b Q>>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
     {
         Cursor {
-            current: self.upper_bound_internal(bound),
+            current: self.upper_bound_internal(bound.into()),
             tree: self,
         }
     }
@@ -1565,12 +2415,14 @@ where
     /// below the given bound. If no such element is found then a null
     /// cursor is returned.
     #[inline]
-    pub fn upper_bound_mut<'a, Q: ?Sized + Ord>(&'a mut self, bound: Bound<&Q>) -> CursorMut<'a, A>
+    pub fn upper_bound_mut<'a, 'b, Q: ?Sized + 'b, B>(&'a mut self, bound: B) -> CursorMut<'a, A>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        B: Into<Bound<&'b Q>>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
     {
         CursorMut {
-            current: self.upper_bound_internal(bound),
+            current: self.upper_bound_internal(bound.into()),
             tree: self,
         }
     }
@@ -1587,41 +2439,181 @@ where
     /// Panics if the new element is already linked to a different intrusive
     /// collection.
     #[inline]
-    pub fn insert<'a>(&'a mut self, val: <A::PointerOps as PointerOps>::Pointer) -> CursorMut<'_, A>
+    pub fn insert(&mut self, val: <A::PointerOps as PointerOps>::Pointer) -> CursorMut<'_, A> {
+        unsafe {
+            let new = self.node_from_value(val);
+            self.insert_node(new);
+            CursorMut {
+                current: Some(new),
+                tree: self,
+            }
+        }
+    }
+
+    #[inline]
+    unsafe fn insert_node<'a>(&mut self, new: <A::LinkOps as link_ops::LinkOps>::LinkPtr)
     where
-        <A as KeyAdapter<'a>>::Key: Ord,
+        <A::PointerOps as PointerOps>::Value: 'a,
     {
+        self.insert_node_equal(new, false);
+    }
+
+    // Inserts a node at the position given by its key. If the tree already
+    // contains elements with an equal key then the node is inserted before them
+    // if `front` is true and after them otherwise.
+    #[inline]
+    unsafe fn insert_node_equal<'a>(
+        &mut self,
+        new: <A::LinkOps as link_ops::LinkOps>::LinkPtr,
+        front: bool,
+    ) where
+        <A::PointerOps as PointerOps>::Value: 'a,
+    {
+        let raw = self.adapter.get_value(new);
+        if let Some(root) = self.root {
+            let key = self.adapter.get_sort_key(&*raw);
+            let mut tree = root;
+            loop {
+                let current = self.adapter.get_sort_key(&*self.adapter.get_value(tree));
+                let go_left = match self.adapter.comparator().compare(&key, &current) {
+                    Ordering::Less => true,
+                    Ordering::Equal => front,
+                    Ordering::Greater => false,
+                };
+                if go_left {
+                    if let Some(left) = self.adapter.link_ops().left(tree) {
+                        tree = left;
+                    } else {
+                        insert_left(self.adapter.link_ops_mut(), tree, new, &mut self.root);
+                        break;
+                    }
+                } else {
+                    if let Some(right) = self.adapter.link_ops().right(tree) {
+                        tree = right;
+                    } else {
+                        insert_right(self.adapter.link_ops_mut(), tree, new, &mut self.root);
+                        break;
+                    }
+                }
+            }
+        } else {
+            self.insert_root(new);
+        }
+    }
+
+    /// Inserts a new element into the `RBTree` before all elements with an
+    /// equal key.
+    ///
+    /// Returns a mutable cursor pointing to the newly added element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new element is already linked to a different intrusive
+    /// collection.
+    #[inline]
+    pub fn insert_equal_front<'a>(
+        &'a mut self,
+        val: <A::PointerOps as PointerOps>::Pointer,
+    ) -> CursorMut<'a, A> {
+        unsafe {
+            let new = self.node_from_value(val);
+            self.insert_node_equal(new, true);
+            CursorMut {
+                current: Some(new),
+                tree: self,
+            }
+        }
+    }
+
+    /// Inserts a new element into the `RBTree` after all elements with an
+    /// equal key.
+    ///
+    /// This is the same as `insert`.
+    ///
+    /// Returns a mutable cursor pointing to the newly added element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new element is already linked to a different intrusive
+    /// collection.
+    #[inline]
+    pub fn insert_equal_back<'a>(
+        &'a mut self,
+        val: <A::PointerOps as PointerOps>::Pointer,
+    ) -> CursorMut<'a, A> {
+        self.insert(val)
+    }
+
+    /// Inserts a new element into the `RBTree` unless an element with an
+    /// equal key is already present.
+    ///
+    /// On success, returns a mutable cursor pointing to the newly added
+    /// element. Otherwise the tree is left unchanged and an error is returned
+    /// containing a cursor pointing to the existing element along with the
+    /// rejected `val` parameter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new element is already linked to a different intrusive
+    /// collection.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub fn insert_unique<'a>(
+        &'a mut self,
+        val: <A::PointerOps as PointerOps>::Pointer,
+    ) -> Result<CursorMut<'a, A>, (CursorMut<'a, A>, <A::PointerOps as PointerOps>::Pointer)> {
+        use link_ops::LinkOps;
+
         unsafe {
             let new = self.node_from_value(val);
             let raw = self.adapter.get_value(new);
             if let Some(root) = self.root {
-                let key = self.adapter.get_key(&*raw);
+                let key = self.adapter.get_sort_key(&*raw);
                 let mut tree = root;
                 loop {
-                    let current = &*self.adapter.get_value(tree);
-                    if key < self.adapter.get_key(current) {
-                        if let Some(left) = self.adapter.link_ops().left(tree) {
-                            tree = left;
-                        } else {
-                            insert_left(self.adapter.link_ops_mut(), tree, new, &mut self.root);
-                            break;
+                    let current = self.adapter.get_sort_key(&*self.adapter.get_value(tree));
+                    match self.adapter.comparator().compare(&key, &current) {
+                        Ordering::Less => {
+                            if let Some(left) = self.adapter.link_ops().left(tree) {
+                                tree = left;
+                            } else {
+                                insert_left(self.adapter.link_ops_mut(), tree, new, &mut self.root);
+                                break;
+                            }
                         }
-                    } else {
-                        if let Some(right) = self.adapter.link_ops().right(tree) {
-                            tree = right;
-                        } else {
-                            insert_right(self.adapter.link_ops_mut(), tree, new, &mut self.root);
-                            break;
+                        Ordering::Equal => {
+                            self.adapter.link_ops_mut().release_link(new);
+                            let val = self.adapter.pointer_ops().from_raw(raw);
+                            return Err((
+                                CursorMut {
+                                    current: Some(tree),
+                                    tree: self,
+                                },
+                                val,
+                            ));
+                        }
+                        Ordering::Greater => {
+                            if let Some(right) = self.adapter.link_ops().right(tree) {
+                                tree = right;
+                            } else {
+                                insert_right(
+                                    self.adapter.link_ops_mut(),
+                                    tree,
+                                    new,
+                                    &mut self.root,
+                                );
+                                break;
+                            }
                         }
                     }
                 }
             } else {
                 self.insert_root(new);
             }
-            CursorMut {
+            Ok(CursorMut {
                 current: Some(new),
                 tree: self,
-            }
+            })
         }
     }
 
@@ -1636,16 +2628,16 @@ where
     /// If multiple elements with an identical key are found then an arbitrary
     /// one is returned.
     #[inline]
-    pub fn entry<'a, Q: ?Sized + Ord>(&'a mut self, key: &Q) -> Entry<'a, A>
+    pub fn entry<'a, Q: ?Sized>(&'a mut self, key: &Q) -> Entry<'a, A>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
     {
         unsafe {
             if let Some(root) = self.root {
                 let mut tree = root;
                 loop {
-                    let current = &*self.adapter.get_value(tree);
-                    match key.cmp(self.adapter.get_key(current).borrow()) {
+                    match self.compare_key(key, tree) {
                         Ordering::Less => {
                             if let Some(left) = self.adapter.link_ops().left(tree) {
                                 tree = left;
@@ -1686,39 +2678,470 @@ where
         }
     }
 
+    /// Returns an iterator over all elements with the given key, in the order in
+    /// which they appear in the tree.
+    #[inline]
+    pub fn equal_range<'a, Q: ?Sized>(&'a self, key: &Q) -> Iter<'a, A>
+    where
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        let lower = self.lower_bound_internal(Included(key));
+        if let Some(lower) = lower {
+            if unsafe { self.compare_key(key, lower) } == Ordering::Equal {
+                return Iter {
+                    head: Some(lower),
+                    tail: self.upper_bound_internal(Included(key)),
+                    tree: self,
+                };
+            }
+        }
+        Iter {
+            head: None,
+            tail: None,
+            tree: self,
+        }
+    }
+
+    /// Returns the number of elements with the given key.
+    ///
+    /// This runs in O(log n + k) time, where k is the number of elements with
+    /// the given key.
+    #[inline]
+    pub fn count<'a, Q: ?Sized>(&'a self, key: &Q) -> usize
+    where
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        self.equal_range(key).count()
+    }
+
     /// Constructs a double-ended iterator over a sub-range of elements in the
     /// tree, starting at min, and ending at max. If min is `Unbounded`, then it
     /// will be treated as "negative infinity", and if max is `Unbounded`, then
     /// it will be treated as "positive infinity". Thus
     /// `range(Unbounded, Unbounded)` will yield the whole collection.
+    ///
+    /// Unlike `range_of`, this takes the crate's own `Bound` type, the same one
+    /// used by `lower_bound` and `upper_bound`, and allows the two ends of the
+    /// range to be given using different lookup types.
     #[inline]
-    pub fn range<'a, Min: ?Sized + Ord, Max: ?Sized + Ord>(
+    pub fn range<'a, Min: ?Sized, Max: ?Sized>(
         &'a self,
         min: Bound<&Min>,
         max: Bound<&Max>,
     ) -> Iter<'a, A>
     where
-        <A as KeyAdapter<'a>>::Key: Borrow<Min> + Borrow<Max>,
-        <A as KeyAdapter<'a>>::Key: Ord,
+        <A as ComparatorAdapter<'a>>::Comparator: CompareWith<Min, <A as ComparatorAdapter<'a>>::SortKey>
+            + CompareWith<Max, <A as ComparatorAdapter<'a>>::SortKey>,
     {
         let lower = self.lower_bound_internal(min);
         let upper = self.upper_bound_internal(max);
+        let (head, tail) = self.clamp_range(lower, upper);
+        Iter {
+            head,
+            tail,
+            tree: self,
+        }
+    }
+
+    /// Constructs a double-ended iterator over a sub-range of elements in the
+    /// tree. The range may be given using range syntax, such as `a..b` or
+    /// `..=b`, or as a pair of `core::ops::Bound`s. Thus `range_of(..)` will
+    /// yield the whole collection.
+    ///
+    /// Pairs of the crate's own `Bound` type do not implement `RangeBounds`;
+    /// pass them to `range` instead, or convert them with `Into` first.
+    #[inline]
+    pub fn range_of<'a, Q: ?Sized, R>(&'a self, range: R) -> Iter<'a, A>
+    where
+        R: RangeBounds<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        let (head, tail) = self.range_internal(range);
+        Iter {
+            head,
+            tail,
+            tree: self,
+        }
+    }
 
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    fn range_internal<'a, Q: ?Sized, R>(
+        &self,
+        range: R,
+    ) -> (
+        Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+    )
+    where
+        R: RangeBounds<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+        <A::PointerOps as PointerOps>::Value: 'a,
+    {
+        let lower = self.lower_bound_internal(range.start_bound().into());
+        let upper = self.upper_bound_internal(range.end_bound().into());
+        self.clamp_range(lower, upper)
+    }
+
+    // Returns the ends of a range given its lower and upper bound, or an empty
+    // range if the bounds cross.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    fn clamp_range(
+        &self,
+        lower: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        upper: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+    ) -> (
+        Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+    ) {
         if let (Some(lower), Some(upper)) = (lower, upper) {
-            let lower_key = unsafe { self.adapter.get_key(&*self.adapter.get_value(lower)) };
-            let upper_key = unsafe { self.adapter.get_key(&*self.adapter.get_value(upper)) };
-            if upper_key >= lower_key {
-                return Iter {
-                    head: Some(lower),
-                    tail: Some(upper),
+            if unsafe { self.compare_nodes(upper, lower) } != Ordering::Less {
+                return (Some(lower), Some(upper));
+            }
+        }
+        (None, None)
+    }
+
+    /// Returns a `RangeEnds` over a sub-range of elements in the tree, which
+    /// allows elements to be removed from either end of the range.
+    ///
+    /// Elements can only be removed from the ends of the range. To remove
+    /// elements from the middle of a range, use a `CursorMut` obtained from
+    /// `lower_bound_mut` instead.
+    ///
+    /// The range is interpreted in the same way as in `range_of`.
+    #[inline]
+    pub fn range_ends<'a, Q: ?Sized, R>(&'a mut self, range: R) -> RangeEnds<'a, A>
+    where
+        R: RangeBounds<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        let (head, tail) = self.range_internal(range);
+        RangeEnds {
+            head,
+            tail,
+            tree: self,
+        }
+    }
+
+    /// Removes all elements in a sub-range of the tree and returns an
+    /// iterator over pointers to them, in key order.
+    ///
+    /// The range is interpreted in the same way as in `range_of`. If the
+    /// iterator is dropped before it is exhausted then the remaining elements
+    /// in the range are removed and dropped.
+    #[inline]
+    pub fn drain_range<'a, Q: ?Sized, R>(&'a mut self, range: R) -> DrainRange<'a, A>
+    where
+        R: RangeBounds<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        DrainRange {
+            range: self.range_ends(range),
+        }
+    }
+
+    /// Removes and drops all elements in a sub-range of the tree.
+    ///
+    /// The range is interpreted in the same way as in `range_of`.
+    #[inline]
+    pub fn remove_range<'a, Q: ?Sized, R>(&'a mut self, range: R)
+    where
+        R: RangeBounds<Q>,
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+    {
+        let mut range = self.range_ends(range);
+        while range.pop_front().is_some() {}
+    }
+
+    /// Splits the tree into two at the given key. Returns a new `RBTree`
+    /// containing all elements whose key is greater than or equal to `key`,
+    /// while `self` keeps the elements whose key is less than `key`.
+    ///
+    /// This runs in O(log n) time.
+    #[inline]
+    pub fn split_off<'a, Q: ?Sized>(&'a mut self, key: &Q) -> RBTree<A>
+    where
+        <A as ComparatorAdapter<'a>>::Comparator:
+            CompareWith<Q, <A as ComparatorAdapter<'a>>::SortKey>,
+        A: Clone,
+    {
+        use link_ops::LinkOps;
+
+        unsafe {
+            let height = black_height(self.adapter.link_ops(), self.root);
+            let [(lower, _), (upper, _)] = split(
+                &mut self.adapter,
+                self.root,
+                height,
+                &mut |adapter: &A, x| {
+                    let current = adapter.get_sort_key(&*adapter.get_value(x));
+                    adapter.comparator().compare_with(key, &current) != Ordering::Greater
+                },
+            );
+            self.root = lower;
+            let mut tree = RBTree {
+                root: upper,
+                adapter: self.adapter.clone(),
+            };
+            tree.adapter.link_ops_mut().renew_owner();
+            if let Some(root) = upper {
+                adopt(tree.adapter.link_ops_mut(), root);
+            }
+            tree
+        }
+    }
+
+    /// Moves all elements from `other` into `self`, leaving `other` empty.
+    ///
+    /// If all the keys in one tree are ordered before all the keys in the
+    /// other tree then the two trees are joined in O(log n) time. Otherwise the
+    /// elements of both trees are merged in O(n + m) time.
+    ///
+    /// Elements of `other` which have the same key as elements of `self` are
+    /// placed after them.
+    #[inline]
+    pub fn append(&mut self, other: &mut RBTree<A>) {
+        use link_ops::LinkOps;
+
+        if let Some(other_root) = other.root {
+            unsafe { adopt(self.adapter.link_ops_mut(), other_root) };
+        }
+        let (self_root, other_root) = match (self.root, other.root.take()) {
+            (_, None) => return,
+            (None, other_root) => {
+                self.root = other_root;
+                return;
+            }
+            (Some(self_root), Some(other_root)) => (self_root, other_root),
+        };
+
+        unsafe {
+            let link_ops = self.adapter.link_ops();
+            let self_first = first_child(link_ops, self_root);
+            let self_last = last_child(link_ops, self_root);
+            let other_first = first_child(link_ops, other_root);
+            let other_last = last_child(link_ops, other_root);
+
+            // Check whether the key ranges of the trees are disjoint, in which
+            // case the first element of the upper tree is taken out and used
+            // to join the two trees.
+            let disjoint = if self.compare_nodes(self_last, other_first) != Ordering::Greater {
+                Some((self_root, other_root, other_first))
+            } else if self.compare_nodes(other_last, self_first) == Ordering::Less {
+                Some((other_root, self_root, self_first))
+            } else {
+                None
+            };
+
+            if let Some((lower, upper, mid)) = disjoint {
+                let link_ops = self.adapter.link_ops_mut();
+                let mut upper = Some(upper);
+                remove(link_ops, mid, &mut upper);
+                link_ops.acquire_link(mid);
+                let lower_height = black_height(link_ops, Some(lower));
+                let upper_height = black_height(link_ops, upper);
+                let (root, _) = join(
+                    link_ops,
+                    Some(lower),
+                    lower_height,
+                    mid,
+                    upper,
+                    upper_height,
+                );
+                self.root = root;
+            } else {
+                let (head, len) = merge_into_chain(
+                    &mut self.adapter,
+                    Some(self_root),
+                    Some(other_root),
+                    &mut |adapter: &A, x, y| {
+                        let x = adapter.get_sort_key(&*adapter.get_value(x));
+                        let y = adapter.get_sort_key(&*adapter.get_value(y));
+                        adapter.comparator().compare(&x, &y) == Ordering::Less
+                    },
+                );
+                self.root = build_from_chain(self.adapter.link_ops_mut(), head, len);
+            }
+        }
+    }
+
+    /// Returns an iterator over the elements which are in `self` or in
+    /// `other`, in key order. Elements with equal keys in both trees are only
+    /// yielded once, from `self`.
+    ///
+    /// Elements with equal keys are matched one-to-one between the two trees,
+    /// so a key which appears multiple times is yielded as many times as it
+    /// appears in whichever tree has more of it.
+    ///
+    /// The trees are compared using the comparator of `self`. Iterating over
+    /// all elements takes O(n + m) time.
+    ///
+    /// Both trees must use the same adapter type, since elements from either
+    /// tree are yielded as the same type. This also applies to the other set
+    /// operations. To compare a tree with one using a different adapter, look
+    /// up the keys of one tree in the other using `find` instead.
+    #[inline]
+    pub fn union<'a>(&'a self, other: &'a RBTree<A>) -> Union<'a, A> {
+        Union {
+            merge: MergeIter::new(self, other),
+        }
+    }
+
+    /// Returns an iterator over the elements of `self` which have an element
+    /// with an equal key in `other`, in key order.
+    ///
+    /// Elements with equal keys are matched one-to-one between the two trees,
+    /// as in `union`.
+    #[inline]
+    pub fn intersection<'a>(&'a self, other: &'a RBTree<A>) -> Intersection<'a, A> {
+        Intersection {
+            merge: MergeIter::new(self, other),
+        }
+    }
+
+    /// Returns an iterator over the elements of `self` which do not have an
+    /// element with an equal key in `other`, in key order.
+    ///
+    /// Elements with equal keys are matched one-to-one between the two trees,
+    /// as in `union`.
+    #[inline]
+    pub fn difference<'a>(&'a self, other: &'a RBTree<A>) -> Difference<'a, A> {
+        Difference {
+            merge: MergeIter::new(self, other),
+        }
+    }
+
+    /// Returns an iterator over the elements which are in `self` or in
+    /// `other` but not in both, in key order.
+    ///
+    /// Elements with equal keys are matched one-to-one between the two trees,
+    /// as in `union`.
+    #[inline]
+    pub fn symmetric_difference<'a>(&'a self, other: &'a RBTree<A>) -> SymmetricDifference<'a, A> {
+        SymmetricDifference {
+            merge: MergeIter::new(self, other),
+        }
+    }
+
+    /// Returns `true` if every element of `self` has an element with an equal
+    /// key in `other`.
+    #[inline]
+    pub fn is_subset(&self, other: &RBTree<A>) -> bool {
+        self.difference(other).next().is_none()
+    }
+
+    /// Returns `true` if `self` and `other` have no keys in common.
+    #[inline]
+    pub fn is_disjoint(&self, other: &RBTree<A>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+impl<A: Adapter> RBTree<A>
+where
+    A::LinkOps: RBTreeOps + OwnerLinkOps,
+{
+    /// Returns a `CursorMut` pointing to the given element, or `None` if the
+    /// element is not part of this tree.
+    ///
+    /// This is a safe version of `cursor_mut_from_ptr`, made possible by a
+    /// link type which records its owner, such as `TaggedLink`.
+    #[inline]
+    pub fn cursor_mut_for(
+        &mut self,
+        value: &<A::PointerOps as PointerOps>::Value,
+    ) -> Option<CursorMut<'_, A>> {
+        unsafe {
+            let link = self.adapter.get_link(value);
+            if self.adapter.link_ops().is_owned(link) {
+                Some(CursorMut {
+                    current: Some(link),
                     tree: self,
-                };
+                })
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Removes the given element from the tree without searching for it, or
+    /// returns `None` if the element is not part of this tree.
+    ///
+    /// Unlike `remove`, this does not search for the element by key, so it
+    /// removes exactly the given element even if other elements have an
+    /// equal key.
+    #[inline]
+    pub fn remove_value(
+        &mut self,
+        value: &<A::PointerOps as PointerOps>::Value,
+    ) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        self.cursor_mut_for(value)?.remove()
+    }
+}
+
+impl<A: Adapter> RBTree<A>
+where
+    A::LinkOps: RBTreeOps + LinkedListOps,
+{
+    /// Converts the `RBTree` into a `LinkedList` containing the same elements
+    /// in the same order.
+    ///
+    /// The elements are re-threaded through their existing links in O(n) time
+    /// without being unlinked or converted back into owned pointers, which
+    /// requires the link type to support both collections.
+    #[inline]
+    pub fn into_sorted_list(self) -> LinkedList<A> {
+        let tree = ManuallyDrop::new(self);
+        let mut adapter = unsafe { ptr::read(&tree.adapter) };
+        unsafe {
+            let (head, _) = merge_into_chain(&mut adapter, tree.root, None, &mut |_, _, _| false);
+
+            // The chain is threaded through the right pointers of the tree, so
+            // read the next element before overwriting the node with list
+            // pointers.
+            let mut prev = None;
+            let mut current = head;
+            while let Some(x) = current {
+                current = adapter.link_ops().right(x);
+                LinkedListOps::set_prev(adapter.link_ops_mut(), x, prev);
+                LinkedListOps::set_next(adapter.link_ops_mut(), x, current);
+                prev = Some(x);
             }
+            LinkedList::from_raw_parts(head, prev, adapter)
         }
-        Iter {
-            head: None,
-            tail: None,
-            tree: self,
+    }
+
+    /// Converts a `LinkedList` into a balanced `RBTree` containing the same
+    /// elements, in O(n) time.
+    ///
+    /// As with `from_sorted_iter`, the elements of the list must already be in
+    /// ascending key order. Failure to do this may lead to `find`,
+    /// `upper_bound`, `lower_bound` and `range` returning incorrect results.
+    ///
+    /// The elements are re-threaded through their existing links without being
+    /// unlinked or converted back into owned pointers.
+    #[inline]
+    pub fn from_sorted_list(list: LinkedList<A>) -> RBTree<A> {
+        let (head, _, mut adapter) = list.into_raw_parts();
+        unsafe {
+            let mut len = 0;
+            let mut current = head;
+            while let Some(x) = current {
+                current = LinkedListOps::next(adapter.link_ops(), x);
+                adapter.link_ops_mut().set_right(x, current);
+                len += 1;
+            }
+            let root = build_from_chain(adapter.link_ops_mut(), head, len);
+            RBTree { root, adapter }
         }
     }
 }
@@ -1908,6 +3331,78 @@ where
             Entry::Vacant(entry) => entry.insert(default()),
         }
     }
+
+    /// Calls the given function with the element if the entry is occupied,
+    /// and returns the entry unchanged.
+    ///
+    /// Since elements are shared through their pointers, any modification must
+    /// be done through interior mutability and must not change the key of the
+    /// element.
+    pub fn and_modify<F>(self, f: F) -> Entry<'a, A>
+    where
+        F: FnOnce(&<A::PointerOps as PointerOps>::Value),
+    {
+        if let Entry::Occupied(ref entry) = self {
+            f(unsafe { entry.get().unwrap_unchecked() });
+        }
+        self
+    }
+}
+
+/// An occupied entry of an `RBTree`, pointing to an existing element.
+///
+/// This is returned by `first_entry` and `last_entry`.
+pub struct OccupiedEntry<'a, A: Adapter>
+where
+    A::LinkOps: RBTreeOps,
+{
+    cursor: CursorMut<'a, A>,
+}
+
+impl<'a, A: Adapter + 'a> OccupiedEntry<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    /// Returns a reference to the element in the entry.
+    #[inline]
+    pub fn get(&self) -> &<A::PointerOps as PointerOps>::Value {
+        unsafe { self.cursor.get().unwrap_unchecked() }
+    }
+
+    /// Converts the entry into a `CursorMut` pointing to the element.
+    #[inline]
+    pub fn into_cursor(self) -> CursorMut<'a, A> {
+        self.cursor
+    }
+
+    /// Removes the element from the `RBTree` and returns a pointer to it.
+    #[inline]
+    pub fn remove(mut self) -> <A::PointerOps as PointerOps>::Pointer {
+        unsafe { self.cursor.remove().unwrap_unchecked() }
+    }
+
+    /// Replaces the element in the entry with a new one, returning a pointer
+    /// to the old element. The new element is put in the same position in the
+    /// tree.
+    ///
+    /// The new element must have the same key as the old one. Failure to do
+    /// this may lead to `find`, `upper_bound`, `lower_bound` and `range`
+    /// returning incorrect results.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new element is already linked to a different intrusive
+    /// collection.
+    #[inline]
+    pub fn replace(
+        &mut self,
+        val: <A::PointerOps as PointerOps>::Pointer,
+    ) -> <A::PointerOps as PointerOps>::Pointer {
+        match self.cursor.replace_with(val) {
+            Ok(old) => old,
+            Err(_) => unreachable!(),
+        }
+    }
 }
 
 // =============================================================================
@@ -2027,42 +3522,343 @@ where
         }
     }
 }
-impl<A: Adapter> DoubleEndedIterator for IntoIter<A>
+impl<A: Adapter> DoubleEndedIterator for IntoIter<A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        use link_ops::LinkOps;
+
+        let tail = self.tail?;
+        let link_ops = self.tree.adapter.link_ops_mut();
+        unsafe {
+            // Remove the node from the tree. Since tail is always the
+            // right-most node, we can infer the following:
+            // - tail.right is null.
+            // - tail is a right child of its parent (or the root node).
+            if let Some(parent) = link_ops.parent(tail) {
+                link_ops.set_right(parent, link_ops.left(tail));
+            } else {
+                self.tree.root = link_ops.left(tail);
+                if link_ops.left(tail).is_none() {
+                    self.tail = None;
+                }
+            }
+            if let Some(left) = link_ops.left(tail) {
+                link_ops.set_parent(left, link_ops.parent(tail));
+                self.tail = Some(last_child(link_ops, left));
+            } else {
+                self.tail = link_ops.parent(tail);
+            }
+            link_ops.release_link(tail);
+            Some(
+                self.tree
+                    .adapter
+                    .pointer_ops()
+                    .from_raw(self.tree.adapter.get_value(tail)),
+            )
+        }
+    }
+}
+
+// =============================================================================
+// RangeEnds
+// =============================================================================
+
+/// A view of a sub-range of elements in a `RBTree`, which allows elements to
+/// be removed from either end of the range.
+///
+/// Unlike `CursorMut`, this does not support moving through the range or
+/// removing elements from its middle: it only shrinks the range from its ends.
+pub struct RangeEnds<'a, A: Adapter>
+where
+    A::LinkOps: RBTreeOps,
+{
+    head: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+    tail: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+    tree: &'a mut RBTree<A>,
+}
+
+impl<'a, A: Adapter> RangeEnds<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    /// Returns `true` if there are no elements left in the range.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Returns a reference to the first element in the range, or `None` if
+    /// the range is empty.
+    #[inline]
+    pub fn front(&self) -> Option<&<A::PointerOps as PointerOps>::Value> {
+        Some(unsafe { &*self.tree.adapter.get_value(self.head?) })
+    }
+
+    /// Returns a reference to the last element in the range, or `None` if the
+    /// range is empty.
+    #[inline]
+    pub fn back(&self) -> Option<&<A::PointerOps as PointerOps>::Value> {
+        Some(unsafe { &*self.tree.adapter.get_value(self.tail?) })
+    }
+
+    /// Gets an iterator over the elements left in the range.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, A> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            tree: self.tree,
+        }
+    }
+
+    // Unlinks the given element, which must be part of the range, from the
+    // tree and returns a pointer to it.
+    #[inline]
+    unsafe fn remove_node(
+        &mut self,
+        node: <A::LinkOps as link_ops::LinkOps>::LinkPtr,
+    ) -> <A::PointerOps as PointerOps>::Pointer {
+        remove(self.tree.adapter.link_ops_mut(), node, &mut self.tree.root);
+        self.tree
+            .adapter
+            .pointer_ops()
+            .from_raw(self.tree.adapter.get_value(node))
+    }
+
+    /// Removes the first element in the range from the tree and returns a
+    /// pointer to it, or `None` if the range is empty.
+    #[inline]
+    pub fn pop_front(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        let head = self.head?;
+        if self.head == self.tail {
+            self.head = None;
+            self.tail = None;
+        } else {
+            self.head = unsafe { next(self.tree.adapter.link_ops(), head) };
+        }
+        Some(unsafe { self.remove_node(head) })
+    }
+
+    /// Removes the last element in the range from the tree and returns a
+    /// pointer to it, or `None` if the range is empty.
+    #[inline]
+    pub fn pop_back(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        let tail = self.tail?;
+        if self.head == self.tail {
+            self.head = None;
+            self.tail = None;
+        } else {
+            self.tail = unsafe { prev(self.tree.adapter.link_ops(), tail) };
+        }
+        Some(unsafe { self.remove_node(tail) })
+    }
+}
+
+// =============================================================================
+// DrainRange
+// =============================================================================
+
+/// An iterator which removes a sub-range of elements from a `RBTree`.
+///
+/// Any elements which have not been yielded when the iterator is dropped are
+/// removed from the tree and dropped.
+pub struct DrainRange<'a, A: Adapter>
+where
+    A::LinkOps: RBTreeOps,
+{
+    range: RangeEnds<'a, A>,
+}
+impl<'a, A: Adapter> Iterator for DrainRange<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    type Item = <A::PointerOps as PointerOps>::Pointer;
+
+    #[inline]
+    fn next(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        self.range.pop_front()
+    }
+}
+impl<'a, A: Adapter> DoubleEndedIterator for DrainRange<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
+        self.range.pop_back()
+    }
+}
+impl<'a, A: Adapter> Drop for DrainRange<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    #[inline]
+    fn drop(&mut self) {
+        while self.range.pop_front().is_some() {}
+    }
+}
+
+// =============================================================================
+// Set operations
+// =============================================================================
+
+// Walks two trees in parallel, yielding the next element of either tree along
+// with which tree it came from. Elements with equal keys are yielded together.
+struct MergeIter<'a, A: Adapter>
+where
+    A::LinkOps: RBTreeOps,
+{
+    a: Peekable<Iter<'a, A>>,
+    b: Peekable<Iter<'a, A>>,
+    adapter: &'a A,
+}
+
+// An element yielded by `MergeIter`.
+enum Merged<T> {
+    Left(T),
+    Right(T),
+    Both(T),
+}
+
+impl<'a, A: for<'b> ComparatorAdapter<'b>> MergeIter<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    #[inline]
+    fn new(a: &'a RBTree<A>, b: &'a RBTree<A>) -> MergeIter<'a, A> {
+        MergeIter {
+            a: a.iter().peekable(),
+            b: b.iter().peekable(),
+            adapter: &a.adapter,
+        }
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<Merged<&'a <A::PointerOps as PointerOps>::Value>> {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) => {
+                let a = self.adapter.get_sort_key(a);
+                let b = self.adapter.get_sort_key(b);
+                self.adapter.comparator().compare(&a, &b)
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        match order {
+            Ordering::Less => self.a.next().map(Merged::Left),
+            Ordering::Greater => self.b.next().map(Merged::Right),
+            Ordering::Equal => {
+                self.b.next();
+                self.a.next().map(Merged::Both)
+            }
+        }
+    }
+}
+
+/// An iterator over the union of two `RBTree`s.
+///
+/// This `struct` is created by the `union` method on `RBTree`.
+pub struct Union<'a, A: Adapter>
+where
+    A::LinkOps: RBTreeOps,
+{
+    merge: MergeIter<'a, A>,
+}
+impl<'a, A: for<'b> ComparatorAdapter<'b>> Iterator for Union<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    type Item = &'a <A::PointerOps as PointerOps>::Value;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a <A::PointerOps as PointerOps>::Value> {
+        match self.merge.next()? {
+            Merged::Left(x) | Merged::Right(x) | Merged::Both(x) => Some(x),
+        }
+    }
+}
+
+/// An iterator over the intersection of two `RBTree`s.
+///
+/// This `struct` is created by the `intersection` method on `RBTree`.
+pub struct Intersection<'a, A: Adapter>
+where
+    A::LinkOps: RBTreeOps,
+{
+    merge: MergeIter<'a, A>,
+}
+impl<'a, A: for<'b> ComparatorAdapter<'b>> Iterator for Intersection<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    type Item = &'a <A::PointerOps as PointerOps>::Value;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a <A::PointerOps as PointerOps>::Value> {
+        loop {
+            // Stop early once either tree is exhausted.
+            self.merge.a.peek()?;
+            self.merge.b.peek()?;
+            if let Merged::Both(x) = self.merge.next()? {
+                return Some(x);
+            }
+        }
+    }
+}
+
+/// An iterator over the difference of two `RBTree`s.
+///
+/// This `struct` is created by the `difference` method on `RBTree`.
+pub struct Difference<'a, A: Adapter>
+where
+    A::LinkOps: RBTreeOps,
+{
+    merge: MergeIter<'a, A>,
+}
+impl<'a, A: for<'b> ComparatorAdapter<'b>> Iterator for Difference<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    type Item = &'a <A::PointerOps as PointerOps>::Value;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a <A::PointerOps as PointerOps>::Value> {
+        loop {
+            // Stop early once the left tree is exhausted.
+            self.merge.a.peek()?;
+            if let Merged::Left(x) = self.merge.next()? {
+                return Some(x);
+            }
+        }
+    }
+}
+
+/// An iterator over the symmetric difference of two `RBTree`s.
+///
+/// This `struct` is created by the `symmetric_difference` method on `RBTree`.
+pub struct SymmetricDifference<'a, A: Adapter>
 where
     A::LinkOps: RBTreeOps,
 {
-    #[inline]
-    fn next_back(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
-        use link_ops::LinkOps;
+    merge: MergeIter<'a, A>,
+}
+impl<'a, A: for<'b> ComparatorAdapter<'b>> Iterator for SymmetricDifference<'a, A>
+where
+    A::LinkOps: RBTreeOps,
+{
+    type Item = &'a <A::PointerOps as PointerOps>::Value;
 
-        let tail = self.tail?;
-        let link_ops = self.tree.adapter.link_ops_mut();
-        unsafe {
-            // Remove the node from the tree. Since tail is always the
-            // right-most node, we can infer the following:
-            // - tail.right is null.
-            // - tail is a right child of its parent (or the root node).
-            if let Some(parent) = link_ops.parent(tail) {
-                link_ops.set_right(parent, link_ops.left(tail));
-            } else {
-                self.tree.root = link_ops.left(tail);
-                if link_ops.left(tail).is_none() {
-                    self.tail = None;
-                }
-            }
-            if let Some(left) = link_ops.left(tail) {
-                link_ops.set_parent(left, link_ops.parent(tail));
-                self.tail = Some(last_child(link_ops, left));
-            } else {
-                self.tail = link_ops.parent(tail);
+    #[inline]
+    fn next(&mut self) -> Option<&'a <A::PointerOps as PointerOps>::Value> {
+        loop {
+            match self.merge.next()? {
+                Merged::Left(x) | Merged::Right(x) => return Some(x),
+                Merged::Both(_) => {}
             }
-            link_ops.release_link(tail);
-            Some(
-                self.tree
-                    .adapter
-                    .pointer_ops()
-                    .from_raw(self.tree.adapter.get_value(tail)),
-            )
         }
     }
 }
@@ -2073,10 +3869,15 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{Entry, KeyAdapter, Link, PointerOps, RBTree};
+    use super::{Color, Entry, Link, PointerOps, RBTree, RBTreeOps};
+    use crate::Adapter;
     use crate::Bound::*;
+    use crate::{ComparatorAdapter, KeyAdapter, NaturalOrder, ReverseOrder};
+    use core::ptr::NonNull;
     use rand::prelude::*;
     use rand_xorshift::XorShiftRng;
+    use std::boxed::Box;
+    use std::cell::Cell;
     use std::fmt;
     use std::rc::Rc;
     use std::vec::Vec;
@@ -2106,6 +3907,44 @@ mod tests {
         })
     }
 
+    // Checks the parent pointers and the red-black invariants of the tree and
+    // returns the keys in order.
+    fn check_tree(t: &RBTree<ObjAdapter>) -> Vec<i32> {
+        fn black_height(
+            link_ops: &super::LinkOps,
+            node: Option<NonNull<Link>>,
+            parent: Option<NonNull<Link>>,
+        ) -> usize {
+            let node = match node {
+                Some(node) => node,
+                None => return 1,
+            };
+            unsafe {
+                assert_eq!(link_ops.parent(node), parent);
+                let left = black_height(link_ops, link_ops.left(node), Some(node));
+                let right = black_height(link_ops, link_ops.right(node), Some(node));
+                assert_eq!(left, right);
+                if link_ops.color(node) == Color::Red {
+                    for child in [link_ops.left(node), link_ops.right(node)].iter() {
+                        if let Some(child) = *child {
+                            assert_eq!(link_ops.color(child), Color::Black);
+                        }
+                    }
+                    left
+                } else {
+                    left + 1
+                }
+            }
+        }
+        if let Some(root) = t.root {
+            assert_eq!(unsafe { t.adapter.link_ops().color(root) }, Color::Black);
+        }
+        black_height(t.adapter.link_ops(), t.root, None);
+        let keys = t.iter().map(|x| x.value).collect::<Vec<_>>();
+        assert!(keys.windows(2).all(|w| w[0] <= w[1]));
+        keys
+    }
+
     #[test]
     fn test_link() {
         let a = make_obj(1);
@@ -2349,105 +4188,105 @@ mod tests {
             vec![90, 80, 70, 60, 50, 40, 30, 20, 10, 0]
         );
         assert_eq!(
-            t.range(Unbounded, Unbounded)
+            t.range(Unbounded::<&i32>, Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![0, 10, 20, 30, 40, 50, 60, 70, 80, 90]
         );
 
         assert_eq!(
-            t.range(Included(&0), Unbounded)
+            t.range(Included(&0), Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![0, 10, 20, 30, 40, 50, 60, 70, 80, 90]
         );
         assert_eq!(
-            t.range(Excluded(&0), Unbounded)
+            t.range(Excluded(&0), Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![10, 20, 30, 40, 50, 60, 70, 80, 90]
         );
         assert_eq!(
-            t.range(Included(&25), Unbounded)
+            t.range(Included(&25), Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![30, 40, 50, 60, 70, 80, 90]
         );
         assert_eq!(
-            t.range(Excluded(&25), Unbounded)
+            t.range(Excluded(&25), Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![30, 40, 50, 60, 70, 80, 90]
         );
         assert_eq!(
-            t.range(Included(&70), Unbounded)
+            t.range(Included(&70), Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![70, 80, 90]
         );
         assert_eq!(
-            t.range(Excluded(&70), Unbounded)
+            t.range(Excluded(&70), Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![80, 90]
         );
         assert_eq!(
-            t.range(Included(&100), Unbounded)
+            t.range(Included(&100), Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![]
         );
         assert_eq!(
-            t.range(Excluded(&100), Unbounded)
+            t.range(Excluded(&100), Unbounded::<&i32>)
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![]
         );
 
         assert_eq!(
-            t.range(Unbounded, Included(&90))
+            t.range(Unbounded::<&i32>, Included(&90))
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![0, 10, 20, 30, 40, 50, 60, 70, 80, 90]
         );
         assert_eq!(
-            t.range(Unbounded, Excluded(&90))
+            t.range(Unbounded::<&i32>, Excluded(&90))
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![0, 10, 20, 30, 40, 50, 60, 70, 80]
         );
         assert_eq!(
-            t.range(Unbounded, Included(&25))
+            t.range(Unbounded::<&i32>, Included(&25))
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![0, 10, 20]
         );
         assert_eq!(
-            t.range(Unbounded, Excluded(&25))
+            t.range(Unbounded::<&i32>, Excluded(&25))
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![0, 10, 20]
         );
         assert_eq!(
-            t.range(Unbounded, Included(&70))
+            t.range(Unbounded::<&i32>, Included(&70))
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![0, 10, 20, 30, 40, 50, 60, 70]
         );
         assert_eq!(
-            t.range(Unbounded, Excluded(&70))
+            t.range(Unbounded::<&i32>, Excluded(&70))
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![0, 10, 20, 30, 40, 50, 60]
         );
         assert_eq!(
-            t.range(Unbounded, Included(&-1))
+            t.range(Unbounded::<&i32>, Included(&-1))
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![]
         );
         assert_eq!(
-            t.range(Unbounded, Excluded(&-1))
+            t.range(Unbounded::<&i32>, Excluded(&-1))
                 .map(|x| x.value)
                 .collect::<Vec<_>>(),
             vec![]
@@ -2604,11 +4443,11 @@ mod tests {
                 );
             }
             {
-                let c = t.upper_bound(Unbounded);
+                let c = t.upper_bound(Unbounded::<&i32>);
                 assert_eq!(c.get().map(|x| x.value), Some(90));
             }
             {
-                let c = t.upper_bound_mut(Unbounded);
+                let c = t.upper_bound_mut(Unbounded::<&i32>);
                 assert_eq!(c.get().map(|x| x.value), Some(90));
             }
             {
@@ -2648,11 +4487,11 @@ mod tests {
                 );
             }
             {
-                let c = t.lower_bound(Unbounded);
+                let c = t.lower_bound(Unbounded::<&i32>);
                 assert_eq!(c.get().map(|x| x.value), Some(0));
             }
             {
-                let c = t.lower_bound_mut(Unbounded);
+                let c = t.lower_bound_mut(Unbounded::<&i32>);
                 assert_eq!(c.get().map(|x| x.value), Some(0));
             }
             {
@@ -2702,6 +4541,207 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_from_sorted_iter() {
+        for n in 0..70 {
+            let t = RBTree::from_sorted_iter(ObjAdapter::new(), (0..n).map(make_obj));
+            assert_eq!(check_tree(&t), (0..n).collect::<Vec<_>>());
+            assert!(t.iter().all(|x| x.link.is_linked()));
+        }
+
+        let v = [1, 1, 2, 3, 3, 3]
+            .iter()
+            .map(|&x| make_obj(x))
+            .collect::<Vec<_>>();
+        let mut t = RBTree::from_sorted_iter(ObjAdapter::new(), v.iter().cloned());
+        assert_eq!(check_tree(&t), [1, 1, 2, 3, 3, 3]);
+        t.insert(make_obj(2));
+        t.find_mut(&3).remove();
+        assert_eq!(check_tree(&t), [1, 1, 2, 2, 3, 3]);
+    }
+
+    #[test]
+    fn test_insert_with_hint() {
+        let mut t = RBTree::new(ObjAdapter::new());
+        {
+            // Ascending insertion through a null cursor appends at the back.
+            let mut c = t.cursor_mut();
+            for x in 0..50 {
+                c.insert_with_hint(make_obj(x * 2));
+            }
+            assert!(c.is_null());
+        }
+        assert_eq!(check_tree(&t), (0..50).map(|x| x * 2).collect::<Vec<_>>());
+
+        {
+            // Descending insertion through a cursor at the front.
+            let mut c = t.front_mut();
+            c.insert_with_hint(make_obj(-1));
+            c.move_prev();
+            c.insert_with_hint(make_obj(-2));
+            assert_eq!(c.get().unwrap().value, -1);
+        }
+
+        {
+            // A correct hint in the middle of the tree.
+            let mut c = t.find_mut(&40);
+            c.insert_with_hint(make_obj(39));
+            c.insert_with_hint(make_obj(40));
+            assert_eq!(c.get().unwrap().value, 40);
+            assert_eq!(c.peek_prev().get().unwrap().value, 40);
+        }
+
+        {
+            // Wrong hints fall back to a full search.
+            let mut c = t.find_mut(&10);
+            c.insert_with_hint(make_obj(77));
+            c.insert_with_hint(make_obj(-5));
+            c.move_next();
+            c.insert_with_hint(make_obj(11));
+        }
+        let mut expected = (0..50).map(|x| x * 2).collect::<Vec<_>>();
+        expected.extend_from_slice(&[-1, -2, 39, 40, 77, -5, 11]);
+        expected.sort();
+        assert_eq!(check_tree(&t), expected);
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_split_off() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+        for n in 0..40 {
+            for key in -1..=n {
+                let mut v = (0..n).collect::<Vec<_>>();
+                v.shuffle(&mut rng);
+                let mut t = RBTree::new(ObjAdapter::new());
+                for &x in v.iter() {
+                    t.insert(make_obj(x));
+                }
+
+                let t2 = t.split_off(&key);
+                assert_eq!(check_tree(&t), (0..key.max(0)).collect::<Vec<_>>());
+                assert_eq!(check_tree(&t2), (key.max(0)..n).collect::<Vec<_>>());
+            }
+        }
+
+        let mut t = RBTree::from_sorted_iter(
+            ObjAdapter::new(),
+            [1, 2, 2, 2, 3].iter().map(|&x| make_obj(x)),
+        );
+        let t2 = t.split_off(&2);
+        assert_eq!(check_tree(&t), [1]);
+        assert_eq!(check_tree(&t2), [2, 2, 2, 3]);
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_append() {
+        fn make_tree(values: &[i32]) -> RBTree<ObjAdapter> {
+            let mut t = RBTree::new(ObjAdapter::new());
+            for &x in values {
+                t.insert(make_obj(x));
+            }
+            t
+        }
+
+        for n in 0..30 {
+            for m in 0..30 {
+                // Disjoint ranges, in both orders.
+                let lower = (0..n).collect::<Vec<_>>();
+                let upper = (n..n + m).collect::<Vec<_>>();
+                let mut t = make_tree(&lower);
+                let mut t2 = make_tree(&upper);
+                t.append(&mut t2);
+                assert!(t2.is_empty());
+                assert_eq!(check_tree(&t), (0..n + m).collect::<Vec<_>>());
+
+                let mut t = make_tree(&upper);
+                let mut t2 = make_tree(&lower);
+                t.append(&mut t2);
+                assert!(t2.is_empty());
+                assert_eq!(check_tree(&t), (0..n + m).collect::<Vec<_>>());
+
+                // Interleaved ranges.
+                let evens = (0..n).map(|x| x * 2).collect::<Vec<_>>();
+                let odds = (0..m).map(|x| x * 2 + 1).collect::<Vec<_>>();
+                let mut t = make_tree(&evens);
+                let mut t2 = make_tree(&odds);
+                t.append(&mut t2);
+                assert!(t2.is_empty());
+                let mut expected = evens.clone();
+                expected.extend_from_slice(&odds);
+                expected.sort();
+                assert_eq!(check_tree(&t), expected);
+            }
+        }
+
+        // Elements with equal keys from `other` go after those of `self`.
+        let a = make_obj(1);
+        let b = make_obj(1);
+        let mut t = RBTree::new(ObjAdapter::new());
+        let mut t2 = RBTree::new(ObjAdapter::new());
+        t.insert(a.clone());
+        t2.insert(b.clone());
+        t.append(&mut t2);
+        assert_eq!(check_tree(&t), [1, 1]);
+        assert!(Rc::ptr_eq(&t.front().clone_pointer().unwrap(), &a));
+        assert!(Rc::ptr_eq(&t.back().clone_pointer().unwrap(), &b));
+    }
+
+    #[test]
+    fn test_multimap() {
+        let mut t = RBTree::new(ObjAdapter::new());
+        let a = make_obj(2);
+        let b = make_obj(2);
+        let c = make_obj(2);
+        let d = make_obj(2);
+        t.insert(make_obj(1));
+        t.insert(make_obj(3));
+        assert_eq!(t.insert_equal_back(a.clone()).get().unwrap().value, 2);
+        t.insert_equal_back(b.clone());
+        t.insert_equal_front(c.clone());
+        t.insert(d.clone());
+
+        let order = t
+            .equal_range(&2)
+            .map(|x| x as *const Obj)
+            .collect::<Vec<_>>();
+        let expected = [&c, &a, &b, &d]
+            .iter()
+            .map(|x| x.as_ref() as *const Obj)
+            .collect::<Vec<_>>();
+        assert_eq!(order, expected);
+        assert_eq!(t.equal_range(&2).rev().count(), 4);
+        assert_eq!(t.count(&2), 4);
+        assert_eq!(t.count(&1), 1);
+        assert_eq!(t.count(&0), 0);
+        assert_eq!(t.count(&4), 0);
+        assert_eq!(t.equal_range(&1).map(|x| x.value).collect::<Vec<_>>(), [1]);
+        assert_eq!(t.equal_range(&0).count(), 0);
+        assert_eq!(RBTree::new(ObjAdapter::new()).count(&0), 0);
+
+        let e = make_obj(3);
+        match t.insert_unique(e.clone()) {
+            Ok(_) => unreachable!(),
+            Err((cursor, val)) => {
+                assert_eq!(cursor.get().unwrap().value, 3);
+                assert!(Rc::ptr_eq(&val, &e));
+            }
+        }
+        assert!(!e.link.is_linked());
+        assert_eq!(t.count(&3), 1);
+
+        let f = make_obj(4);
+        match t.insert_unique(f.clone()) {
+            Ok(cursor) => assert_eq!(cursor.get().unwrap().value, 4),
+            Err(_) => unreachable!(),
+        }
+        assert!(f.link.is_linked());
+        let mut t2 = RBTree::new(ObjAdapter::new());
+        assert!(t2.insert_unique(make_obj(0)).is_ok());
+        assert_eq!(check_tree(&t), [1, 2, 2, 2, 2, 3, 4],);
+    }
+
     #[test]
     fn test_fast_clear() {
         let mut t = RBTree::new(ObjAdapter::new());
@@ -2773,6 +4813,288 @@ mod tests {
         assert!(f.link.is_linked());
     }
 
+    #[test]
+    fn test_comparator() {
+        intrusive_adapter!(RevAdapter = Rc<Obj>: Obj { link: Link });
+        impl<'a> ComparatorAdapter<'a> for RevAdapter {
+            type SortKey = i32;
+            type Comparator = ReverseOrder<NaturalOrder>;
+            fn get_sort_key(&self, value: &'a Obj) -> i32 {
+                value.value
+            }
+            fn comparator(&self) -> &ReverseOrder<NaturalOrder> {
+                &ReverseOrder(NaturalOrder)
+            }
+        }
+
+        let mut t = RBTree::new(RevAdapter::new());
+        for v in &[3, 1, 4, 1, 5, 9, 2, 6] {
+            t.insert(make_obj(*v));
+        }
+        let values = |t: &RBTree<RevAdapter>| t.iter().map(|x| x.value).collect::<Vec<_>>();
+        assert_eq!(values(&t), [9, 6, 5, 4, 3, 2, 1, 1]);
+        assert_eq!(t.find(&4).get().unwrap().value, 4);
+        assert!(t.find(&7).is_null());
+        assert_eq!(t.lower_bound(Included(&7)).get().unwrap().value, 6);
+        assert_eq!(t.upper_bound(Excluded(&4)).get().unwrap().value, 5);
+        assert_eq!(
+            t.range(Included(&5), Excluded(&2))
+                .map(|x| x.value)
+                .collect::<Vec<_>>(),
+            [5, 4, 3]
+        );
+        assert_eq!(t.count(&1), 2);
+
+        let other =
+            RBTree::from_sorted_iter(RevAdapter::new(), [8, 7, 0].iter().map(|v| make_obj(*v)));
+        t.append(&mut { other });
+        assert_eq!(values(&t), [9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 0]);
+        let low = t.split_off(&4);
+        assert_eq!(values(&t), [9, 8, 7, 6, 5]);
+        assert_eq!(values(&low), [4, 3, 2, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_partition_point() {
+        // Elements are ordered by insertion only, without a KeyAdapter.
+        intrusive_adapter!(PlainAdapter = Rc<Obj>: Obj { link: Link });
+        let mut t = RBTree::new(PlainAdapter::new());
+        for v in 0..10 {
+            let mut c = t.back_mut();
+            c.insert_after(make_obj(v * 10));
+        }
+
+        assert_eq!(t.partition_point(|x| x.value < 35).get().unwrap().value, 40);
+        assert_eq!(t.partition_point(|x| x.value < 40).get().unwrap().value, 40);
+        assert_eq!(t.partition_point(|_| false).get().unwrap().value, 0);
+        assert!(t.partition_point(|_| true).is_null());
+        assert!(RBTree::new(PlainAdapter::new())
+            .partition_point(|_| true)
+            .is_null());
+
+        assert_eq!(t.find_by(|x| x.value.cmp(&70)).get().unwrap().value, 70);
+        assert!(t.find_by(|x| x.value.cmp(&75)).is_null());
+
+        t.partition_point_mut(|x| x.value < 50).remove();
+        t.find_by_mut(|x| x.value.cmp(&0)).remove();
+        assert_eq!(
+            t.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [10, 20, 30, 40, 60, 70, 80, 90]
+        );
+    }
+
+    #[test]
+    fn test_map_api() {
+        let mut t = RBTree::new(ObjAdapter::new());
+        assert!(t.pop_first().is_none());
+        assert!(t.first_entry().is_none());
+        assert!(t.last_entry().is_none());
+        for v in 1..=5 {
+            t.insert(make_obj(v));
+        }
+
+        assert_eq!(t.get(&3).unwrap().value, 3);
+        assert!(t.get(&6).is_none());
+        assert!(t.contains_key(&5));
+        assert!(!t.contains_key(&0));
+
+        let c = t.remove(&3).unwrap();
+        assert_eq!(c.value, 3);
+        assert!(!c.link.is_linked());
+        assert!(t.remove(&3).is_none());
+
+        assert_eq!(t.pop_first().unwrap().value, 1);
+        assert_eq!(t.pop_last().unwrap().value, 5);
+        assert_eq!(t.first_entry().unwrap().get().value, 2);
+        assert_eq!(t.last_entry().unwrap().remove().value, 4);
+        assert_eq!(check_tree(&t), [2]);
+
+        // Replace the element with another one with the same key.
+        let b = make_obj(2);
+        let old = t.first_entry().unwrap().replace(b.clone());
+        assert!(!old.link.is_linked());
+        assert!(b.link.is_linked());
+        assert!(Rc::ptr_eq(&t.front().clone_pointer().unwrap(), &b));
+
+        let mut modified = false;
+        t.entry(&2).and_modify(|x| modified = x.value == 2);
+        assert!(modified);
+        t.entry(&7)
+            .and_modify(|_| unreachable!())
+            .or_insert(make_obj(7));
+        match t.entry(&2) {
+            Entry::Occupied(mut c) => assert!(Rc::ptr_eq(&c.remove().unwrap(), &b)),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert_eq!(check_tree(&t), [7]);
+    }
+
+    #[test]
+    fn test_range_mut() {
+        use core::ops::Bound::{Excluded, Included, Unbounded};
+
+        let mut t = RBTree::new(ObjAdapter::new());
+        for v in 0..10 {
+            t.insert(make_obj(v));
+        }
+
+        let mut r = t.range_ends::<i32, _>((Included(&2), Excluded(&6)));
+        assert_eq!(r.front().unwrap().value, 2);
+        assert_eq!(r.back().unwrap().value, 5);
+        assert_eq!(r.pop_back().unwrap().value, 5);
+        assert_eq!(r.pop_front().unwrap().value, 2);
+        assert_eq!(r.iter().map(|x| x.value).collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(check_tree(&t), [0, 1, 3, 4, 6, 7, 8, 9]);
+
+        let drained = t
+            .drain_range::<i32, _>((Excluded(&1), Included(&7)))
+            .map(|x| x.value)
+            .collect::<Vec<_>>();
+        assert_eq!(drained, [3, 4, 6, 7]);
+        assert_eq!(check_tree(&t), [0, 1, 8, 9]);
+
+        let a = make_obj(5);
+        t.insert(a.clone());
+        let mut d = t.drain_range::<i32, _>(..);
+        assert_eq!(d.next_back().unwrap().value, 9);
+        drop(d);
+        assert!(t.is_empty());
+        assert!(!a.link.is_linked());
+        assert_eq!(Rc::strong_count(&a), 1);
+
+        for v in 0..10 {
+            t.insert(make_obj(v));
+        }
+        t.remove_range::<i32, _>((Included(&7), Unbounded));
+        t.remove_range::<i32, _>((Included(&4), Included(&1)));
+        assert!(t
+            .range_ends::<i32, _>((Excluded(&3), Excluded(&4)))
+            .is_empty());
+        assert_eq!(check_tree(&t), [0, 1, 2, 3, 4, 5, 6]);
+        t.remove_range::<i32, _>((Unbounded, Excluded(&3)));
+        assert_eq!(check_tree(&t), [3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let tree = |values: &[i32]| {
+            RBTree::from_sorted_iter(ObjAdapter::new(), values.iter().map(|v| make_obj(*v)))
+        };
+        let values =
+            |iter: &mut dyn Iterator<Item = &Obj>| iter.map(|x| x.value).collect::<Vec<_>>();
+        let a = tree(&[1, 2, 2, 3, 5, 8]);
+        let b = tree(&[2, 3, 4, 8, 9]);
+        let empty = tree(&[]);
+
+        assert_eq!(values(&mut a.union(&b)), [1, 2, 2, 3, 4, 5, 8, 9]);
+        assert_eq!(values(&mut a.intersection(&b)), [2, 3, 8]);
+        assert_eq!(values(&mut a.difference(&b)), [1, 2, 5]);
+        assert_eq!(values(&mut b.difference(&a)), [4, 9]);
+        assert_eq!(values(&mut a.symmetric_difference(&b)), [1, 2, 4, 5, 9]);
+        assert_eq!(values(&mut a.union(&empty)), [1, 2, 2, 3, 5, 8]);
+        assert_eq!(values(&mut empty.intersection(&a)), []);
+
+        // Elements common to both trees are yielded from the left tree.
+        let x = a.intersection(&b).next().unwrap();
+        assert!(core::ptr::eq(x, a.lower_bound(Included(&2)).get().unwrap()));
+
+        assert!(tree(&[2, 8]).is_subset(&b));
+        assert!(!tree(&[2, 2]).is_subset(&b));
+        assert!(empty.is_subset(&a));
+        assert!(!a.is_subset(&b));
+        assert!(tree(&[0, 6, 7]).is_disjoint(&a));
+        assert!(!b.is_disjoint(&a));
+        assert!(empty.is_disjoint(&empty));
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        use core::ops::Bound::{Excluded, Included};
+
+        let mut t = RBTree::new(ObjAdapter::new());
+        for v in 0..10 {
+            t.insert(make_obj(v));
+        }
+        let values = |iter: super::Iter<'_, ObjAdapter>| iter.map(|x| x.value).collect::<Vec<_>>();
+        assert_eq!(
+            values(t.range_of::<i32, _>(..)),
+            (0..10).collect::<Vec<_>>()
+        );
+        assert_eq!(values(t.range_of(3..6)), [3, 4, 5]);
+        assert_eq!(values(t.range_of(3..=6)), [3, 4, 5, 6]);
+        assert_eq!(values(t.range_of(..2)), [0, 1]);
+        assert_eq!(values(t.range_of(8..)), [8, 9]);
+        assert_eq!(values(t.range_of::<i32, _>(&8..)), [8, 9]);
+        assert_eq!(
+            values(t.range_of::<i32, _>((Excluded(&4), Included(&5)))),
+            [5]
+        );
+
+        // Both crate and core bounds are accepted for single bounds.
+        assert_eq!(
+            t.lower_bound(crate::Bound::Excluded(&4))
+                .get()
+                .unwrap()
+                .value,
+            5
+        );
+        assert_eq!(t.upper_bound(Excluded(&4)).get().unwrap().value, 3);
+        assert_eq!(
+            t.upper_bound_mut(crate::Bound::Unbounded::<&i32>)
+                .get()
+                .unwrap()
+                .value,
+            9
+        );
+
+        let bound = crate::Bound::Included(7);
+        assert_eq!(core::ops::Bound::from(bound.as_ref()), Included(&7));
+        assert_eq!(crate::Bound::from(Excluded(7)), crate::Bound::Excluded(7));
+
+        assert_eq!(
+            t.drain_range(7..).map(|x| x.value).collect::<Vec<_>>(),
+            [7, 8, 9]
+        );
+        t.remove_range(..=1);
+        assert_eq!(check_tree(&t), [2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_sorted_list() {
+        let mut t = RBTree::new(ObjAdapter::new());
+        let objs = (0..20).map(make_obj).collect::<Vec<_>>();
+        for x in objs.iter().rev() {
+            t.insert(x.clone());
+        }
+
+        let mut list = t.into_sorted_list();
+        assert_eq!(
+            list.iter().map(|x| x.value).collect::<Vec<_>>(),
+            (0..20).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            list.iter().rev().map(|x| x.value).collect::<Vec<_>>(),
+            (0..20).rev().collect::<Vec<_>>()
+        );
+        assert!(objs.iter().all(|x| x.link.is_linked()));
+
+        // The list owns the elements and can be modified as usual.
+        assert_eq!(list.pop_front().unwrap().value, 0);
+        list.push_back(make_obj(20));
+
+        let t = RBTree::from_sorted_list(list);
+        assert_eq!(check_tree(&t), (1..21).collect::<Vec<_>>());
+        assert_eq!(t.find(&7).get().unwrap().value, 7);
+        assert_eq!(Rc::strong_count(&objs[7]), 2);
+        drop(t);
+        assert_eq!(Rc::strong_count(&objs[7]), 1);
+        assert!(!objs[7].link.is_linked());
+
+        let empty = RBTree::new(ObjAdapter::new()).into_sorted_list();
+        assert!(empty.is_empty());
+        assert!(RBTree::from_sorted_list(empty).is_empty());
+    }
+
     #[test]
     fn test_non_static() {
         #[derive(Clone)]
@@ -2844,4 +5166,203 @@ mod tests {
     fn test_clone_pointer_arc() {
         test_clone_pointer!(Arc, std::sync::Arc);
     }
+
+    #[test]
+    fn test_remove_value() {
+        use crate::TaggedLink;
+        use std::panic::{self, AssertUnwindSafe};
+
+        struct Tagged {
+            link: TaggedLink<Link>,
+            value: i32,
+        }
+        intrusive_adapter!(TaggedAdapter = Rc<Tagged>: Tagged { link: TaggedLink<Link> });
+        impl<'a> KeyAdapter<'a> for TaggedAdapter {
+            type Key = i32;
+            fn get_key(&self, value: &'a Tagged) -> i32 {
+                value.value
+            }
+        }
+        let make = |value| {
+            Rc::new(Tagged {
+                link: TaggedLink::new(Link::new()),
+                value,
+            })
+        };
+        let values = |t: &RBTree<TaggedAdapter>| t.iter().map(|x| x.value).collect::<Vec<_>>();
+
+        let objs = (0..6).map(|x| make(x / 2)).collect::<Vec<_>>();
+        let mut t = RBTree::new(TaggedAdapter::new());
+        let mut u = RBTree::new(TaggedAdapter::new());
+        for x in &objs[..4] {
+            t.insert(x.clone());
+        }
+        for x in &objs[4..] {
+            u.insert(x.clone());
+        }
+
+        // The exact element is removed, not just one with an equal key.
+        assert!(u.remove_value(&objs[1]).is_none());
+        assert!(Rc::ptr_eq(&t.remove_value(&objs[1]).unwrap(), &objs[1]));
+        assert!(t.remove_value(&objs[1]).is_none());
+        assert!(t.cursor_mut_for(&objs[0]).is_some());
+        assert_eq!(values(&t), [0, 1, 1]);
+
+        let mut v = t.split_off(&1);
+        assert!(t.remove_value(&objs[2]).is_none());
+        assert!(v.cursor_mut_for(&objs[0]).is_none());
+        t.append(&mut u);
+        assert!(u.remove_value(&objs[4]).is_none());
+        u.insert(objs[1].clone());
+        assert!(t.remove_value(&objs[1]).is_none());
+        assert!(Rc::ptr_eq(&t.remove_value(&objs[5]).unwrap(), &objs[5]));
+        assert!(Rc::ptr_eq(&v.remove_value(&objs[3]).unwrap(), &objs[3]));
+        assert_eq!(values(&t), [0, 2]);
+        assert_eq!(values(&u), [0]);
+        assert_eq!(values(&v), [1]);
+
+        // An empty tree split off from `t` doesn't share its owner tag.
+        let mut w = t.split_off(&5);
+        assert!(w.remove_value(&objs[0]).is_none());
+        assert_eq!(values(&t), [0, 2]);
+
+        // Elements left behind by a panicking `dispose` don't belong to `t`.
+        let result = panic::catch_unwind(AssertUnwindSafe(|| t.clear_and_dispose(|_| panic!())));
+        assert!(result.is_err());
+        assert!(t.is_empty());
+        assert!(objs[0].link.is_linked() || objs[4].link.is_linked());
+        assert!(t.remove_value(&objs[0]).is_none());
+        assert!(t.remove_value(&objs[4]).is_none());
+    }
+
+    struct CellObj {
+        link: Link,
+        key: Cell<i32>,
+    }
+    intrusive_adapter!(CellObjAdapter = Box<CellObj>: CellObj { link: Link });
+    impl<'a> KeyAdapter<'a> for CellObjAdapter {
+        type Key = i32;
+        fn get_key(&self, value: &'a CellObj) -> i32 {
+            value.key.get()
+        }
+    }
+    fn make_cell_tree(keys: &[i32]) -> RBTree<CellObjAdapter> {
+        let mut t = RBTree::new(CellObjAdapter::new());
+        for &key in keys {
+            t.insert(Box::new(CellObj {
+                link: Link::new(),
+                key: Cell::new(key),
+            }));
+        }
+        t
+    }
+
+    #[test]
+    fn test_update_key() {
+        let mut t = make_cell_tree(&[10, 20, 30, 40]);
+        let keys = |t: &RBTree<CellObjAdapter>| t.iter().map(|x| x.key.get()).collect::<Vec<_>>();
+
+        // Stays between its neighbours.
+        let mut c = t.find_mut(&20);
+        c.update_key(|x| x.key.set(25));
+        assert_eq!(c.get().unwrap().key.get(), 25);
+        assert_eq!(keys(&t), [10, 25, 30, 40]);
+
+        // Moves past its neighbours, and after elements with an equal key.
+        let mut c = t.find_mut(&10);
+        c.update_key(|x| x.key.set(30));
+        assert_eq!(c.get().unwrap().key.get(), 30);
+        c.move_prev();
+        assert_eq!(c.get().unwrap().key.get(), 30);
+        assert_eq!(keys(&t), [25, 30, 30, 40]);
+        t.find_mut(&40).update_key(|x| x.key.set(0));
+        assert_eq!(keys(&t), [0, 25, 30, 30]);
+        assert_eq!(t.find(&0).get().unwrap().key.get(), 0);
+
+        let mut c = t.back_mut();
+        c.move_next();
+        c.update_key(|_| unreachable!());
+    }
+
+    #[test]
+    fn test_update_data() {
+        use crate::DataMut;
+
+        struct Counter {
+            link: Link,
+            data: (i32, u32),
+        }
+        unsafe impl DataMut for Counter {
+            type Data = (i32, u32);
+            fn data_mut(&mut self) -> &mut (i32, u32) {
+                &mut self.data
+            }
+        }
+        intrusive_adapter!(CounterAdapter = Box<Counter>: Counter { link: Link });
+        impl<'a> KeyAdapter<'a> for CounterAdapter {
+            type Key = i32;
+            fn get_key(&self, value: &'a Counter) -> i32 {
+                value.data.0
+            }
+        }
+
+        let mut t = RBTree::new(CounterAdapter::new());
+        for i in 0..4 {
+            t.insert(Box::new(Counter {
+                link: Link::new(),
+                data: (i * 10, 0),
+            }));
+        }
+        let data = |t: &RBTree<CounterAdapter>| t.iter().map(|x| x.data).collect::<Vec<_>>();
+
+        t.find_mut(&10).update_data(|data| data.1 += 1);
+        assert_eq!(data(&t), [(0, 0), (10, 1), (20, 0), (30, 0)]);
+
+        // Changing the key moves the element.
+        let mut c = t.front_mut();
+        c.update_data(|data| *data = (25, 2));
+        assert_eq!(c.get().unwrap().data, (25, 2));
+        assert_eq!(data(&t), [(10, 1), (20, 0), (25, 2), (30, 0)]);
+        assert!(t.find(&25).get().is_some());
+
+        let mut c = t.back_mut();
+        c.move_next();
+        c.update_data(|_| unreachable!());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "out of order")]
+    fn test_find_out_of_order() {
+        let t = make_cell_tree(&[10, 20, 30, 40, 50, 60, 70]);
+        unsafe {
+            let right = t.adapter.link_ops().right(t.root.unwrap()).unwrap();
+            (*t.adapter.get_value(right)).key.set(0);
+        }
+        t.find(&100);
+    }
+
+    #[test]
+    fn test_dispose() {
+        let objs: Vec<_> = [4, 2, 6, 1, 3, 5, 7].iter().map(|&v| make_obj(v)).collect();
+        let mut t = RBTree::new(ObjAdapter::new());
+        for obj in &objs {
+            t.insert(obj.clone());
+        }
+        let mut disposed = Vec::new();
+        assert!(t.pop_first_and_dispose(|x| disposed.push(x.value)));
+        assert!(t
+            .find_mut(&4)
+            .remove_and_dispose(|x| disposed.push(x.value)));
+        assert!(!t.cursor_mut().remove_and_dispose(|_| unreachable!()));
+        assert_eq!(disposed, [1, 4]);
+
+        t.clear_and_dispose(|x| disposed.push(x.value));
+        assert!(t.is_empty());
+        disposed[2..].sort();
+        assert_eq!(disposed, [1, 4, 2, 3, 5, 6, 7]);
+        assert!(objs.iter().all(|x| Rc::strong_count(x) == 1));
+        assert!(objs.iter().all(|x| !x.link.is_linked()));
+        assert!(!t.pop_first_and_dispose(|_| unreachable!()));
+    }
 }
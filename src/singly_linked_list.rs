@@ -10,11 +10,14 @@
 
 use core::cell::Cell;
 use core::fmt;
-use core::ptr::NonNull;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
 
 use crate::link_ops::{self, DefaultLinkOps};
-use crate::pointer_ops::PointerOps;
-use crate::xor_linked_list::XorLinkedListOps;
+use crate::linked_list::{LinkedList, LinkedListOps};
+use crate::pointer_ops::{DataMut, PinnedPointerOps, PointerOps, UniquePointerOps};
+use crate::xor_linked_list::{XorLinkedList, XorLinkedListOps};
 use crate::Adapter;
 
 // =============================================================================
@@ -89,6 +92,19 @@ impl DefaultLinkOps for Link {
 // An object containing a link can be sent to another thread if it is unlinked.
 unsafe impl Send for Link {}
 
+// Freeing an object which is still linked leaves a dangling pointer in the
+// collection, so turn this into a panic when debugging.
+#[cfg(feature = "debug-unsafe-ref")]
+impl Drop for Link {
+    #[inline]
+    fn drop(&mut self) {
+        assert!(
+            !self.is_linked(),
+            "`SinglyLinkedListLink` dropped while still linked into a `SinglyLinkedList`"
+        );
+    }
+}
+
 // Provide an implementation of Clone which simply initializes the new link as
 // unlinked. This allows structs containing a link to derive Clone.
 impl Clone for Link {
@@ -288,6 +304,19 @@ unsafe fn splice<T: SinglyLinkedListOps>(
     }
 }
 
+// Records that the links of the chain starting at `head` now belong to
+// `link_ops`, after they were moved in from another list.
+#[inline]
+unsafe fn adopt<T: SinglyLinkedListOps>(link_ops: &mut T, head: T::LinkPtr) {
+    if T::TRACKS_OWNER {
+        let mut current = Some(head);
+        while let Some(x) = current {
+            link_ops.adopt_link(x);
+            current = link_ops.next(x);
+        }
+    }
+}
+
 // =============================================================================
 // Cursor, CursorMut
 // =============================================================================
@@ -477,6 +506,22 @@ where
         }
     }
 
+    /// Removes the next element from the `SinglyLinkedList` and passes it to
+    /// `dispose`.
+    ///
+    /// This behaves like `remove_next`, except that the removed element is
+    /// handed to `dispose` instead of being returned, and the cursor is not
+    /// moved.
+    ///
+    /// Returns `true` if an element was removed.
+    #[inline]
+    pub fn remove_next_and_dispose<F>(&mut self, dispose: F) -> bool
+    where
+        F: FnOnce(<A::PointerOps as PointerOps>::Pointer),
+    {
+        self.remove_next().map(dispose).is_some()
+    }
+
     /// Removes the next element from the `SinglyLinkedList` and inserts
     /// another object in its place.
     ///
@@ -556,6 +601,7 @@ where
     pub fn splice_after(&mut self, mut list: SinglyLinkedList<A>) {
         if let Some(head) = list.head {
             unsafe {
+                adopt(self.list.adapter.link_ops_mut(), head);
                 let next = if let Some(current) = self.current {
                     self.list.adapter.link_ops().next(current)
                 } else {
@@ -602,13 +648,19 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         if let Some(current) = self.current {
             unsafe {
-                let list = SinglyLinkedList {
+                let mut list = SinglyLinkedList {
                     head: self.list.adapter.link_ops().next(current),
                     adapter: self.list.adapter.clone(),
                 };
                 self.list.adapter.link_ops_mut().set_next(current, None);
+                list.adapter.link_ops_mut().renew_owner();
+                if let Some(head) = list.head {
+                    adopt(list.adapter.link_ops_mut(), head);
+                }
                 list
             }
         } else {
@@ -617,11 +669,67 @@ where
                 adapter: self.list.adapter.clone(),
             };
             self.list.head = None;
+            self.list.adapter.link_ops_mut().renew_owner();
             list
         }
     }
 }
 
+impl<'a, A: Adapter> Cursor<'a, A>
+where
+    A::LinkOps: SinglyLinkedListOps,
+    A::PointerOps: PinnedPointerOps,
+{
+    /// Returns a pinned reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This is only available for collections of pinned pointers such as
+    /// `Pin<Box<T>>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_pin(&self) -> Option<Pin<&'a <A::PointerOps as PointerOps>::Value>> {
+        self.get().map(|value| unsafe { Pin::new_unchecked(value) })
+    }
+}
+
+impl<'a, A: Adapter> CursorMut<'a, A>
+where
+    A::LinkOps: SinglyLinkedListOps,
+    A::PointerOps: PinnedPointerOps,
+{
+    /// Returns a pinned reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This is only available for collections of pinned pointers such as
+    /// `Pin<Box<T>>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_pin(&self) -> Option<Pin<&<A::PointerOps as PointerOps>::Value>> {
+        self.get().map(|value| unsafe { Pin::new_unchecked(value) })
+    }
+}
+
+impl<'a, A: Adapter> CursorMut<'a, A>
+where
+    A::LinkOps: SinglyLinkedListOps,
+    A::PointerOps: UniquePointerOps,
+    <A::PointerOps as PointerOps>::Value: DataMut,
+{
+    /// Returns a mutable reference to the data of the object that the cursor
+    /// is currently pointing to.
+    ///
+    /// This is only available for collections of uniquely owned pointers such
+    /// as `Box<T>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_data_mut(
+        &mut self,
+    ) -> Option<&mut <<A::PointerOps as PointerOps>::Value as DataMut>::Data> {
+        let value = unsafe { self.list.adapter.get_value(self.current?) };
+        Some(unsafe { (*(value as *mut <A::PointerOps as PointerOps>::Value)).data_mut() })
+    }
+}
+
 // =============================================================================
 // SinglyLinkedList
 // =============================================================================
@@ -664,6 +772,25 @@ where
         }
     }
 
+    // Creates a `SinglyLinkedList` from the first element of a list that was
+    // threaded through the links by another collection.
+    #[inline]
+    pub(crate) unsafe fn from_raw_parts(
+        head: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        adapter: A,
+    ) -> SinglyLinkedList<A> {
+        SinglyLinkedList { head, adapter }
+    }
+
+    // Takes the `SinglyLinkedList` apart without unlinking its elements, so
+    // that they can be re-threaded into another collection sharing the same
+    // links.
+    #[inline]
+    pub(crate) fn into_raw_parts(self) -> (Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>, A) {
+        let list = ManuallyDrop::new(self);
+        (list.head, unsafe { ptr::read(&list.adapter) })
+    }
+
     /// Creates an empty `SinglyLinkedList`.
     #[cfg(not(feature = "nightly"))]
     #[inline]
@@ -774,17 +901,37 @@ where
     /// converted back to an owned pointer and then dropped.
     #[inline]
     pub fn clear(&mut self) {
+        self.clear_and_dispose(drop);
+    }
+
+    /// Removes all elements from the `SinglyLinkedList`, passing each one to
+    /// `dispose`.
+    ///
+    /// This behaves like `clear`, except that each element is converted back
+    /// to an owned pointer and handed to `dispose` instead of being dropped.
+    /// This can be used to return elements to a pool or to run some custom
+    /// teardown on them.
+    #[inline]
+    pub fn clear_and_dispose<F>(&mut self, mut dispose: F)
+    where
+        F: FnMut(<A::PointerOps as PointerOps>::Pointer),
+    {
         use link_ops::LinkOps;
 
         let mut current = self.head;
         self.head = None;
+        // Renew the owner first, so that elements which are left linked if
+        // `dispose` panics don't appear to belong to this collection.
+        self.adapter.link_ops_mut().renew_owner();
         while let Some(x) = current {
             unsafe {
                 let next = self.adapter.link_ops().next(x);
                 self.adapter.link_ops_mut().release_link(x);
-                self.adapter
-                    .pointer_ops()
-                    .from_raw(self.adapter.get_value(x));
+                dispose(
+                    self.adapter
+                        .pointer_ops()
+                        .from_raw(self.adapter.get_value(x)),
+                );
                 current = next;
             }
         }
@@ -798,7 +945,10 @@ where
     /// `force_unlink` function on them.
     #[inline]
     pub fn fast_clear(&mut self) {
+        use link_ops::LinkOps;
+
         self.head = None;
+        self.adapter.link_ops_mut().renew_owner();
     }
 
     /// Takes all the elements out of the `SinglyLinkedList`, leaving it empty.
@@ -808,11 +958,14 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         let list = SinglyLinkedList {
             head: self.head,
             adapter: self.adapter.clone(),
         };
         self.head = None;
+        self.adapter.link_ops_mut().renew_owner();
         list
     }
 
@@ -829,6 +982,90 @@ where
     pub fn pop_front(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
         self.cursor_mut().remove_next()
     }
+
+    /// Removes the first element of the `SinglyLinkedList` and passes it to
+    /// `dispose`.
+    ///
+    /// Returns `true` if an element was removed, or `false` if the
+    /// `SinglyLinkedList` was empty.
+    #[inline]
+    pub fn pop_front_and_dispose<F>(&mut self, dispose: F) -> bool
+    where
+        F: FnOnce(<A::PointerOps as PointerOps>::Pointer),
+    {
+        self.pop_front().map(dispose).is_some()
+    }
+
+    /// Reverses the order of the elements in the `SinglyLinkedList`.
+    ///
+    /// This takes O(n) time without allocating.
+    #[inline]
+    pub fn reverse(&mut self) {
+        let link_ops = self.adapter.link_ops_mut();
+        let mut prev = None;
+        let mut current = self.head;
+        while let Some(x) = current {
+            unsafe {
+                current = link_ops.next(x);
+                link_ops.set_next(x, prev);
+            }
+            prev = Some(x);
+        }
+        self.head = prev;
+    }
+}
+
+impl<A: Adapter> SinglyLinkedList<A>
+where
+    A::LinkOps: SinglyLinkedListOps + LinkedListOps,
+{
+    /// Converts the `SinglyLinkedList` into a `LinkedList` containing the same
+    /// elements in the same order.
+    ///
+    /// The elements are re-threaded through their existing links in O(n) time
+    /// without being unlinked or converted back into owned pointers, which
+    /// requires the link type to support both collections.
+    #[inline]
+    pub fn into_linked_list(self) -> LinkedList<A> {
+        let (head, mut adapter) = self.into_raw_parts();
+        unsafe {
+            let mut prev = None;
+            let mut current = head;
+            while let Some(x) = current {
+                current = SinglyLinkedListOps::next(adapter.link_ops(), x);
+                LinkedListOps::set_prev(adapter.link_ops_mut(), x, prev);
+                LinkedListOps::set_next(adapter.link_ops_mut(), x, current);
+                prev = Some(x);
+            }
+            LinkedList::from_raw_parts(head, prev, adapter)
+        }
+    }
+}
+
+impl<A: Adapter> SinglyLinkedList<A>
+where
+    A::LinkOps: SinglyLinkedListOps + XorLinkedListOps,
+{
+    /// Converts the `SinglyLinkedList` into a `XorLinkedList` containing the same
+    /// elements in the same order.
+    ///
+    /// The elements are re-threaded through their existing links in O(n) time
+    /// without being unlinked or converted back into owned pointers, which
+    /// requires the link type to support both collections.
+    #[inline]
+    pub fn into_xor_list(self) -> XorLinkedList<A> {
+        let (head, mut adapter) = self.into_raw_parts();
+        unsafe {
+            let mut prev = None;
+            let mut current = head;
+            while let Some(x) = current {
+                current = SinglyLinkedListOps::next(adapter.link_ops(), x);
+                XorLinkedListOps::set(adapter.link_ops_mut(), x, prev, current);
+                prev = Some(x);
+            }
+            XorLinkedList::from_raw_parts(head, prev, adapter)
+        }
+    }
 }
 
 // Allow read-only access to values from multiple threads
@@ -973,6 +1210,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::{Link, SinglyLinkedList};
+    use crate::XorLinkedList;
     use std::fmt;
     use std::format;
     use std::rc::Rc;
@@ -1251,6 +1489,45 @@ mod tests {
         assert!(!d.link1.is_linked());
     }
 
+    #[test]
+    fn test_into_xor_list() {
+        let a = make_obj(2);
+        let mut l = SinglyLinkedList::new(ObjAdapter1::new());
+        l.push_front(a.clone());
+        l.push_front(make_obj(1));
+        l.push_front(make_obj(0));
+
+        let mut x: XorLinkedList<ObjAdapter1> = l.into_xor_list();
+        assert_eq!(x.iter().map(|x| x.value).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(x.back().get().unwrap().value, 2);
+        assert!(a.link1.is_linked());
+        assert_eq!(x.pop_back().unwrap().value, 2);
+        assert!(!a.link1.is_linked());
+        assert_eq!(x.iter().rev().map(|x| x.value).collect::<Vec<_>>(), [1, 0]);
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut l = SinglyLinkedList::new(ObjAdapter1::new());
+        l.reverse();
+        assert!(l.is_empty());
+        for v in 0..5 {
+            l.push_front(make_obj(v));
+        }
+        assert_eq!(
+            l.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [4, 3, 2, 1, 0]
+        );
+        l.reverse();
+        assert_eq!(
+            l.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(l.pop_front().unwrap().value, 0);
+        l.reverse();
+        assert_eq!(l.iter().map(|x| x.value).collect::<Vec<_>>(), [4, 3, 2, 1]);
+    }
+
     #[test]
     fn test_multi_list() {
         let mut l1 = SinglyLinkedList::new(ObjAdapter1::new());
@@ -1297,6 +1574,44 @@ mod tests {
         assert!(!c.link1.is_linked());
     }
 
+    #[test]
+    fn test_owner() {
+        use crate::link_ops::OwnerLinkOps;
+        use crate::{Adapter, TaggedLink};
+        use std::panic::{self, AssertUnwindSafe};
+
+        struct Tagged {
+            link: TaggedLink<Link>,
+        }
+        intrusive_adapter!(TaggedAdapter = Rc<Tagged>: Tagged { link: TaggedLink<Link> });
+        let a = Rc::new(Tagged {
+            link: TaggedLink::new(Link::new()),
+        });
+        let owns = |l: &SinglyLinkedList<TaggedAdapter>| unsafe {
+            l.adapter.link_ops().is_owned(l.adapter.get_link(&*a))
+        };
+
+        let mut l = SinglyLinkedList::new(TaggedAdapter::new());
+        l.push_front(a.clone());
+        assert!(owns(&l));
+
+        // Empty lists split off from `l` don't share its owner tag.
+        let m = l.front_mut().split_after();
+        assert!(m.is_empty());
+        assert!(!owns(&m));
+        assert!(owns(&l));
+
+        // Elements left behind by a panicking `dispose` don't belong to `l`.
+        l.push_front(Rc::new(Tagged {
+            link: TaggedLink::new(Link::new()),
+        }));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| l.clear_and_dispose(|_| panic!())));
+        assert!(result.is_err());
+        assert!(l.is_empty());
+        assert!(a.link.is_linked());
+        assert!(!owns(&l));
+    }
+
     #[test]
     fn test_non_static() {
         #[derive(Clone)]
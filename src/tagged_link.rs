@@ -0,0 +1,502 @@
+// Copyright 2020 Amari Robinson
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Links which record the collection that they belong to.
+
+use core::cell::Cell;
+use core::fmt;
+use core::ops::Deref;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::link_ops::{self, DefaultLinkOps, OwnerLinkOps};
+use crate::linked_list::LinkedListOps;
+use crate::rbtree::{Color, RBTreeOps};
+use crate::singly_linked_list::SinglyLinkedListOps;
+use crate::xor_linked_list::XorLinkedListOps;
+
+// =============================================================================
+// TaggedLink
+// =============================================================================
+
+/// Intrusive link which wraps another link type and additionally records
+/// which collection it is linked into.
+///
+/// This allows `LinkedList::remove_value`, `RBTree::remove_value` and the
+/// corresponding `cursor_mut_for` functions to safely check that an element
+/// belongs to the collection before removing it.
+///
+/// Moving elements between collections without releasing them, for example
+/// with `CursorMut::splice_after` or `RBTree::append`, needs to update their
+/// tags and therefore takes time linear in the number of moved elements.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::{intrusive_adapter, LinkedList, LinkedListLink, TaggedLink};
+///
+/// struct Test {
+///     link: TaggedLink<LinkedListLink>,
+///     value: u32,
+/// }
+///
+/// intrusive_adapter!(TestAdapter = Box<Test>: Test { link: TaggedLink<LinkedListLink> });
+///
+/// let mut a = LinkedList::new(TestAdapter::new());
+/// let mut b = LinkedList::new(TestAdapter::new());
+/// a.push_back(Box::new(Test { link: TaggedLink::new(LinkedListLink::new()), value: 1 }));
+/// b.push_back(Box::new(Test { link: TaggedLink::new(LinkedListLink::new()), value: 2 }));
+///
+/// let value: *const Test = a.front().get().unwrap();
+/// assert!(b.remove_value(unsafe { &*value }).is_none());
+/// assert_eq!(a.remove_value(unsafe { &*value }).unwrap().value, 1);
+/// ```
+#[repr(C)]
+pub struct TaggedLink<L> {
+    link: L,
+    owner: Cell<usize>,
+}
+
+impl<L> TaggedLink<L> {
+    /// Creates a new `TaggedLink` wrapping the given unlinked link.
+    #[inline]
+    pub const fn new(link: L) -> TaggedLink<L> {
+        TaggedLink {
+            link,
+            owner: Cell::new(0),
+        }
+    }
+}
+
+// Give access to the functions of the wrapped link, such as `is_linked`.
+impl<L> Deref for TaggedLink<L> {
+    type Target = L;
+
+    #[inline]
+    fn deref(&self) -> &L {
+        &self.link
+    }
+}
+
+impl<L> DefaultLinkOps for TaggedLink<L>
+where
+    L: DefaultLinkOps,
+    L::Ops: link_ops::LinkOps<LinkPtr = NonNull<L>>,
+{
+    type Ops = TaggedLinkOps<L::Ops>;
+
+    const NEW: Self::Ops = TaggedLinkOps {
+        ops: L::NEW,
+        owner: 0,
+    };
+}
+
+// Provide an implementation of Clone which simply initializes the new link as
+// unlinked. This allows structs containing a link to derive Clone.
+impl<L: Clone> Clone for TaggedLink<L> {
+    #[inline]
+    fn clone(&self) -> TaggedLink<L> {
+        TaggedLink::new(self.link.clone())
+    }
+}
+
+// Same as above
+impl<L: Default> Default for TaggedLink<L> {
+    #[inline]
+    fn default() -> TaggedLink<L> {
+        TaggedLink::new(L::default())
+    }
+}
+
+// Provide an implementation of Debug so that structs containing a link can
+// still derive Debug.
+impl<L: fmt::Debug> fmt::Debug for TaggedLink<L> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.link.fmt(f)
+    }
+}
+
+// =============================================================================
+// TaggedLinkOps
+// =============================================================================
+
+// Owner tags handed out so far. 0 is reserved for "no owner".
+static NEXT_OWNER: AtomicUsize = AtomicUsize::new(1);
+
+#[inline]
+fn next_owner() -> usize {
+    let mut owner = NEXT_OWNER.load(Ordering::Relaxed);
+    loop {
+        // Reusing a tag would make `is_owned` unsound, so refuse to wrap.
+        let next = owner.checked_add(1).expect("owner tags exhausted");
+        match NEXT_OWNER.compare_exchange_weak(owner, next, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return owner,
+            Err(x) => owner = x,
+        }
+    }
+}
+
+/// `LinkOps` implementation for `TaggedLink`.
+///
+/// Each instance draws a unique owner tag the first time it acquires or adopts
+/// a link, and stores it in every link it owns.
+#[derive(Clone, Copy, Default)]
+pub struct TaggedLinkOps<O> {
+    ops: O,
+    owner: usize,
+}
+
+impl<O> TaggedLinkOps<O> {
+    #[inline]
+    fn owner(&mut self) -> usize {
+        if self.owner == 0 {
+            self.owner = next_owner();
+        }
+        self.owner
+    }
+}
+
+unsafe impl<L, O> link_ops::LinkOps for TaggedLinkOps<O>
+where
+    O: link_ops::LinkOps<LinkPtr = NonNull<L>>,
+{
+    type LinkPtr = NonNull<TaggedLink<L>>;
+
+    #[inline]
+    unsafe fn acquire_link(&mut self, ptr: Self::LinkPtr) -> bool {
+        if self.ops.acquire_link(ptr.cast()) {
+            ptr.as_ref().owner.set(self.owner());
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    unsafe fn release_link(&mut self, ptr: Self::LinkPtr) {
+        ptr.as_ref().owner.set(0);
+        self.ops.release_link(ptr.cast());
+    }
+
+    const TRACKS_OWNER: bool = true;
+
+    #[inline]
+    unsafe fn adopt_link(&mut self, ptr: Self::LinkPtr) {
+        ptr.as_ref().owner.set(self.owner());
+        self.ops.adopt_link(ptr.cast());
+    }
+
+    #[inline]
+    fn renew_owner(&mut self) {
+        self.owner = 0;
+        self.ops.renew_owner();
+    }
+}
+
+unsafe impl<L, O> OwnerLinkOps for TaggedLinkOps<O>
+where
+    O: link_ops::LinkOps<LinkPtr = NonNull<L>>,
+{
+    #[inline]
+    unsafe fn is_owned(&self, ptr: Self::LinkPtr) -> bool {
+        self.owner != 0 && ptr.as_ref().owner.get() == self.owner
+    }
+}
+
+unsafe impl<L, O> LinkedListOps for TaggedLinkOps<O>
+where
+    O: LinkedListOps<LinkPtr = NonNull<L>>,
+{
+    #[inline]
+    unsafe fn next(&self, ptr: Self::LinkPtr) -> Option<Self::LinkPtr> {
+        self.ops.next(ptr.cast()).map(NonNull::cast)
+    }
+
+    #[inline]
+    unsafe fn prev(&self, ptr: Self::LinkPtr) -> Option<Self::LinkPtr> {
+        self.ops.prev(ptr.cast()).map(NonNull::cast)
+    }
+
+    #[inline]
+    unsafe fn set_next(&mut self, ptr: Self::LinkPtr, next: Option<Self::LinkPtr>) {
+        self.ops.set_next(ptr.cast(), next.map(NonNull::cast));
+    }
+
+    #[inline]
+    unsafe fn set_prev(&mut self, ptr: Self::LinkPtr, prev: Option<Self::LinkPtr>) {
+        self.ops.set_prev(ptr.cast(), prev.map(NonNull::cast));
+    }
+}
+
+unsafe impl<L, O> SinglyLinkedListOps for TaggedLinkOps<O>
+where
+    O: SinglyLinkedListOps<LinkPtr = NonNull<L>>,
+{
+    #[inline]
+    unsafe fn next(&self, ptr: Self::LinkPtr) -> Option<Self::LinkPtr> {
+        self.ops.next(ptr.cast()).map(NonNull::cast)
+    }
+
+    #[inline]
+    unsafe fn set_next(&mut self, ptr: Self::LinkPtr, next: Option<Self::LinkPtr>) {
+        self.ops.set_next(ptr.cast(), next.map(NonNull::cast));
+    }
+}
+
+unsafe impl<L, O> XorLinkedListOps for TaggedLinkOps<O>
+where
+    O: XorLinkedListOps<LinkPtr = NonNull<L>>,
+{
+    #[inline]
+    unsafe fn next(
+        &self,
+        ptr: Self::LinkPtr,
+        prev: Option<Self::LinkPtr>,
+    ) -> Option<Self::LinkPtr> {
+        self.ops
+            .next(ptr.cast(), prev.map(NonNull::cast))
+            .map(NonNull::cast)
+    }
+
+    #[inline]
+    unsafe fn prev(
+        &self,
+        ptr: Self::LinkPtr,
+        next: Option<Self::LinkPtr>,
+    ) -> Option<Self::LinkPtr> {
+        self.ops
+            .prev(ptr.cast(), next.map(NonNull::cast))
+            .map(NonNull::cast)
+    }
+
+    #[inline]
+    unsafe fn set(
+        &mut self,
+        ptr: Self::LinkPtr,
+        prev: Option<Self::LinkPtr>,
+        next: Option<Self::LinkPtr>,
+    ) {
+        self.ops
+            .set(ptr.cast(), prev.map(NonNull::cast), next.map(NonNull::cast));
+    }
+
+    #[inline]
+    unsafe fn replace_next_or_prev(
+        &mut self,
+        ptr: Self::LinkPtr,
+        old: Option<Self::LinkPtr>,
+        new: Option<Self::LinkPtr>,
+    ) {
+        self.ops
+            .replace_next_or_prev(ptr.cast(), old.map(NonNull::cast), new.map(NonNull::cast));
+    }
+}
+
+unsafe impl<L, O> RBTreeOps for TaggedLinkOps<O>
+where
+    O: RBTreeOps<LinkPtr = NonNull<L>>,
+{
+    #[inline]
+    unsafe fn left(&self, ptr: Self::LinkPtr) -> Option<Self::LinkPtr> {
+        self.ops.left(ptr.cast()).map(NonNull::cast)
+    }
+
+    #[inline]
+    unsafe fn right(&self, ptr: Self::LinkPtr) -> Option<Self::LinkPtr> {
+        self.ops.right(ptr.cast()).map(NonNull::cast)
+    }
+
+    #[inline]
+    unsafe fn parent(&self, ptr: Self::LinkPtr) -> Option<Self::LinkPtr> {
+        self.ops.parent(ptr.cast()).map(NonNull::cast)
+    }
+
+    #[inline]
+    unsafe fn color(&self, ptr: Self::LinkPtr) -> Color {
+        self.ops.color(ptr.cast())
+    }
+
+    #[inline]
+    unsafe fn set_left(&mut self, ptr: Self::LinkPtr, left: Option<Self::LinkPtr>) {
+        self.ops.set_left(ptr.cast(), left.map(NonNull::cast));
+    }
+
+    #[inline]
+    unsafe fn set_right(&mut self, ptr: Self::LinkPtr, right: Option<Self::LinkPtr>) {
+        self.ops.set_right(ptr.cast(), right.map(NonNull::cast));
+    }
+
+    #[inline]
+    unsafe fn set_parent(&mut self, ptr: Self::LinkPtr, parent: Option<Self::LinkPtr>) {
+        self.ops.set_parent(ptr.cast(), parent.map(NonNull::cast));
+    }
+
+    #[inline]
+    unsafe fn set_color(&mut self, ptr: Self::LinkPtr, color: Color) {
+        self.ops.set_color(ptr.cast(), color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaggedLink;
+    use crate::link_ops::{DefaultLinkOps, LinkOps, OwnerLinkOps};
+    use crate::{LinkedList, LinkedListLink, RBTree, RBTreeLink};
+    use core::ptr::NonNull;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    struct ListObj {
+        link: TaggedLink<LinkedListLink>,
+        value: u32,
+    }
+    intrusive_adapter!(ListAdapter = Rc<ListObj>: ListObj { link: TaggedLink<LinkedListLink> });
+
+    struct TreeObj {
+        link: TaggedLink<RBTreeLink>,
+        value: u32,
+    }
+    intrusive_adapter!(TreeAdapter = Rc<TreeObj>: TreeObj {
+        link: TaggedLink<RBTreeLink>,
+        key: u32 = |x| x.value,
+    });
+
+    fn make_list_obj(value: u32) -> Rc<ListObj> {
+        Rc::new(ListObj {
+            link: TaggedLink::new(LinkedListLink::new()),
+            value,
+        })
+    }
+
+    fn make_tree_obj(value: u32) -> Rc<TreeObj> {
+        Rc::new(TreeObj {
+            link: TaggedLink::new(RBTreeLink::new()),
+            value,
+        })
+    }
+
+    #[test]
+    fn test_owner_tags() {
+        let link = TaggedLink::new(LinkedListLink::new());
+        let ptr = NonNull::from(&link);
+        let mut ops = TaggedLink::<LinkedListLink>::NEW;
+        unsafe {
+            assert!(!ops.is_owned(ptr));
+            assert!(ops.acquire_link(ptr));
+            assert!(ops.is_owned(ptr));
+            assert!(link.is_linked());
+
+            // A renewed copy no longer owns links tagged by the original.
+            let mut copy = ops;
+            copy.renew_owner();
+            assert!(!copy.is_owned(ptr));
+            assert!(ops.is_owned(ptr));
+            copy.adopt_link(ptr);
+            assert!(copy.is_owned(ptr));
+            assert!(!ops.is_owned(ptr));
+
+            copy.release_link(ptr);
+            assert!(!copy.is_owned(ptr));
+            assert!(!link.is_linked());
+        }
+    }
+
+    #[test]
+    fn test_remove_value_other_collection() {
+        let mut l = LinkedList::new(ListAdapter::new());
+        let mut m = LinkedList::new(ListAdapter::new());
+        let a = make_list_obj(1);
+        m.push_back(a.clone());
+        assert!(l.remove_value(&a).is_none());
+        assert!(l.cursor_mut_for(&a).is_none());
+        assert!(a.link.is_linked());
+        assert!(Rc::ptr_eq(&m.remove_value(&a).unwrap(), &a));
+        assert!(m.remove_value(&a).is_none());
+
+        let mut t = RBTree::new(TreeAdapter::new());
+        let mut u = RBTree::new(TreeAdapter::new());
+        let b = make_tree_obj(1);
+        t.insert(make_tree_obj(1));
+        u.insert(b.clone());
+        assert!(t.remove_value(&b).is_none());
+        assert!(t.cursor_mut_for(&b).is_none());
+        assert!(b.link.is_linked());
+        assert_eq!(t.iter().count(), 1);
+        assert!(Rc::ptr_eq(&u.remove_value(&b).unwrap(), &b));
+    }
+
+    #[test]
+    fn test_renew_after_take() {
+        let mut l = LinkedList::new(ListAdapter::new());
+        let a = make_list_obj(1);
+        l.push_back(a.clone());
+        let mut m = l.take();
+        assert!(l.remove_value(&a).is_none());
+
+        // Elements added to the emptied list get a tag distinct from `m`.
+        let b = make_list_obj(2);
+        l.push_back(b.clone());
+        assert_eq!(l.front().get().unwrap().value, 2);
+        assert!(m.remove_value(&b).is_none());
+        assert!(Rc::ptr_eq(&m.remove_value(&a).unwrap(), &a));
+        assert!(Rc::ptr_eq(&l.remove_value(&b).unwrap(), &b));
+
+        let mut t = RBTree::new(TreeAdapter::new());
+        let c = make_tree_obj(1);
+        t.insert(c.clone());
+        let mut u = t.take();
+        assert!(t.remove_value(&c).is_none());
+        assert!(Rc::ptr_eq(&u.remove_value(&c).unwrap(), &c));
+    }
+
+    #[test]
+    fn test_renew_after_split_off() {
+        let objs = (0..4).map(make_tree_obj).collect::<Vec<_>>();
+        let mut t = RBTree::new(TreeAdapter::new());
+        for x in &objs {
+            t.insert(x.clone());
+        }
+        let mut u = t.split_off(&2);
+        assert!(t.remove_value(&objs[2]).is_none());
+        assert!(u.remove_value(&objs[1]).is_none());
+        assert!(Rc::ptr_eq(&t.remove_value(&objs[1]).unwrap(), &objs[1]));
+        assert!(Rc::ptr_eq(&u.remove_value(&objs[2]).unwrap(), &objs[2]));
+
+        // The same holds for an empty tree split off past the last key.
+        let mut v = u.split_off(&10);
+        assert!(v.is_empty());
+        assert!(v.remove_value(&objs[3]).is_none());
+        let w = make_tree_obj(10);
+        v.insert(w.clone());
+        assert!(u.remove_value(&w).is_none());
+        assert!(Rc::ptr_eq(&u.remove_value(&objs[3]).unwrap(), &objs[3]));
+    }
+
+    #[test]
+    fn test_renew_after_fast_clear() {
+        let mut l = LinkedList::new(ListAdapter::new());
+        let a = make_list_obj(1);
+        l.push_back(a.clone());
+        l.fast_clear();
+        assert!(a.link.is_linked());
+        assert!(l.remove_value(&a).is_none());
+        unsafe { a.link.force_unlink() };
+        l.push_back(a.clone());
+        assert!(Rc::ptr_eq(&l.remove_value(&a).unwrap(), &a));
+
+        let mut t = RBTree::new(TreeAdapter::new());
+        let b = make_tree_obj(1);
+        t.insert(b.clone());
+        t.fast_clear();
+        assert!(b.link.is_linked());
+        assert!(t.remove_value(&b).is_none());
+        unsafe { b.link.force_unlink() };
+        t.insert(b.clone());
+        assert!(Rc::ptr_eq(&t.remove_value(&b).unwrap(), &b));
+    }
+}
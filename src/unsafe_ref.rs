@@ -9,6 +9,7 @@
 use crate::alloc::boxed::Box;
 use core::borrow::Borrow;
 use core::fmt;
+use core::mem::ManuallyDrop;
 use core::ops::Deref;
 use core::ptr::NonNull;
 
@@ -21,6 +22,13 @@ use core::ptr::NonNull;
 /// You must guarantee that an object managed by an `UnsafeRef` is not
 /// moved, dropped or accessed through a mutable reference as long as at least
 /// one `UnsafeRef` is pointing to it.
+///
+/// The `debug-unsafe-ref` feature helps tracking down violations of these
+/// rules. It keeps count of the `UnsafeRef` pointing to each object so that
+/// `into_box` can check that it is the only one left, and makes links panic
+/// when they are dropped while still linked into a collection. Note that this
+/// includes objects left linked by `fast_clear`, which must be unlinked with
+/// `force_unlink` before being dropped.
 pub struct UnsafeRef<T: ?Sized> {
     ptr: NonNull<T>,
 }
@@ -30,9 +38,15 @@ impl<T: ?Sized> UnsafeRef<T> {
     ///
     /// # Safety
     ///
-    /// You must ensure that the `UnsafeRef` guarantees are upheld.
+    /// You must ensure that the `UnsafeRef` guarantees are upheld. In
+    /// particular, if the object is owned by a collection of uniquely owned
+    /// pointers such as `Box<T>`, then its data must not be accessed through
+    /// `CursorMut::get_data_mut` or `CursorMut::update_data` of that collection
+    /// while the `UnsafeRef` exists.
     #[inline]
     pub unsafe fn from_raw(val: *const T) -> UnsafeRef<T> {
+        #[cfg(feature = "debug-unsafe-ref")]
+        registry::acquire(val as *const u8 as usize);
         UnsafeRef {
             ptr: NonNull::new_unchecked(val as *mut _),
         }
@@ -41,8 +55,17 @@ impl<T: ?Sized> UnsafeRef<T> {
     /// Converts an `UnsafeRef` into a raw pointer
     #[inline]
     pub fn into_raw(ptr: Self) -> *mut T {
+        let ptr = ManuallyDrop::new(ptr);
+        #[cfg(feature = "debug-unsafe-ref")]
+        registry::release(ptr.addr());
         ptr.ptr.as_ptr()
     }
+
+    #[cfg(feature = "debug-unsafe-ref")]
+    #[inline]
+    fn addr(&self) -> usize {
+        self.ptr.as_ptr() as *const u8 as usize
+    }
 }
 
 #[cfg(feature = "alloc")]
@@ -61,8 +84,16 @@ impl<T: ?Sized> UnsafeRef<T> {
     /// object and that it is not currently a member of any intrusive
     /// collections. This operation is only valid if the `UnsafeRef` was
     /// created using `UnsafeRef::from_box`.
+    ///
+    /// With the `debug-unsafe-ref` feature, this panics if other `UnsafeRef`
+    /// to the object still exist.
     #[inline]
     pub unsafe fn into_box(ptr: Self) -> Box<T> {
+        #[cfg(feature = "debug-unsafe-ref")]
+        assert!(
+            registry::count(ptr.addr()) == 1,
+            "UnsafeRef::into_box called while other UnsafeRef to the object still exist"
+        );
         Box::from_raw(UnsafeRef::into_raw(ptr))
     }
 }
@@ -70,10 +101,20 @@ impl<T: ?Sized> UnsafeRef<T> {
 impl<T: ?Sized> Clone for UnsafeRef<T> {
     #[inline]
     fn clone(&self) -> UnsafeRef<T> {
+        #[cfg(feature = "debug-unsafe-ref")]
+        registry::acquire(self.addr());
         UnsafeRef { ptr: self.ptr }
     }
 }
 
+#[cfg(feature = "debug-unsafe-ref")]
+impl<T: ?Sized> Drop for UnsafeRef<T> {
+    #[inline]
+    fn drop(&mut self) {
+        registry::release(self.addr());
+    }
+}
+
 impl<T: ?Sized> Deref for UnsafeRef<T> {
     type Target = T;
 
@@ -107,3 +148,104 @@ impl<T: fmt::Debug + ?Sized> fmt::Debug for UnsafeRef<T> {
 unsafe impl<T: ?Sized + Send> Send for UnsafeRef<T> {}
 
 unsafe impl<T: ?Sized + Sync> Sync for UnsafeRef<T> {}
+
+// Number of `UnsafeRef` pointing to each object, used to check `into_box`.
+#[cfg(feature = "debug-unsafe-ref")]
+mod registry {
+    use crate::alloc::collections::BTreeMap;
+    use core::cell::UnsafeCell;
+    use core::hint;
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    struct Registry {
+        locked: AtomicBool,
+        counts: UnsafeCell<BTreeMap<usize, usize>>,
+    }
+
+    // The counts are only accessed while holding the lock.
+    unsafe impl Sync for Registry {}
+
+    static REGISTRY: Registry = Registry {
+        locked: AtomicBool::new(false),
+        counts: UnsafeCell::new(BTreeMap::new()),
+    };
+
+    fn with_counts<R>(f: impl FnOnce(&mut BTreeMap<usize, usize>) -> R) -> R {
+        while REGISTRY
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+        let result = f(unsafe { &mut *REGISTRY.counts.get() });
+        REGISTRY.locked.store(false, Ordering::Release);
+        result
+    }
+
+    pub fn acquire(addr: usize) {
+        with_counts(|counts| *counts.entry(addr).or_insert(0) += 1);
+    }
+
+    pub fn release(addr: usize) {
+        with_counts(|counts| {
+            if let Some(count) = counts.get_mut(&addr) {
+                *count -= 1;
+                if *count == 0 {
+                    counts.remove(&addr);
+                }
+            }
+        });
+    }
+
+    pub fn count(addr: usize) -> usize {
+        with_counts(|counts| counts.get(&addr).copied().unwrap_or(0))
+    }
+}
+
+#[cfg(all(test, feature = "debug-unsafe-ref"))]
+mod tests {
+    use super::UnsafeRef;
+    use crate::{intrusive_adapter, LinkedList, LinkedListLink};
+    use std::boxed::Box;
+
+    struct Obj {
+        link: LinkedListLink,
+    }
+    intrusive_adapter!(ObjAdapter = UnsafeRef<Obj>: Obj { link: LinkedListLink });
+
+    fn make_obj() -> UnsafeRef<Obj> {
+        UnsafeRef::from_box(Box::new(Obj {
+            link: LinkedListLink::new(),
+        }))
+    }
+
+    #[test]
+    fn test_into_box() {
+        let a = make_obj();
+        let mut l = LinkedList::new(ObjAdapter::new());
+        l.push_back(a.clone());
+        let b = l.pop_front().unwrap();
+        drop(b);
+        drop(unsafe { UnsafeRef::into_box(a) });
+    }
+
+    #[test]
+    #[should_panic(expected = "other UnsafeRef")]
+    fn test_into_box_shared() {
+        let a = make_obj();
+        let _b = a.clone();
+        drop(unsafe { UnsafeRef::into_box(a) });
+    }
+
+    #[test]
+    #[should_panic(expected = "dropped while still linked into a `LinkedList`")]
+    fn test_drop_linked() {
+        let a = make_obj();
+        // Leak the list, which would otherwise access the freed object while
+        // unwinding.
+        let l = Box::leak(Box::new(LinkedList::new(ObjAdapter::new())));
+        l.push_back(a.clone());
+        drop(unsafe { UnsafeRef::into_box(a) });
+    }
+}
@@ -0,0 +1,395 @@
+// Copyright 2020 Amari Robinson
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Links and adapters for unsized values, such as trait objects and structs
+//! ending with a slice.
+
+use core::cell::Cell;
+use core::fmt;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+use crate::adapter::Adapter;
+use crate::link_ops::{DefaultLinkOps, LinkOps};
+use crate::pointer_ops::{DefaultPointerOps, PointerOps};
+
+// =============================================================================
+// UnsizedLink
+// =============================================================================
+
+/// Intrusive link which wraps another link type and additionally remembers a
+/// pointer to the unsized object of type `T` containing it.
+///
+/// A pointer to an unsized object carries metadata, such as the vtable of a
+/// trait object or the length of a slice, which can't be recovered from the
+/// address of the link alone. `UnsizedLink` records the full pointer when the
+/// object is inserted into a collection, which allows `UnsizedAdapter` to get
+/// back to the object from its link.
+///
+/// See `UnsizedAdapter` for an example.
+#[repr(C)]
+pub struct UnsizedLink<L, T: ?Sized> {
+    link: L,
+    value: Cell<Option<NonNull<T>>>,
+}
+
+impl<L, T: ?Sized> UnsizedLink<L, T> {
+    /// Creates a new `UnsizedLink` wrapping the given unlinked link.
+    #[inline]
+    pub const fn new(link: L) -> UnsizedLink<L, T> {
+        UnsizedLink {
+            link,
+            value: Cell::new(None),
+        }
+    }
+}
+
+// Give access to the functions of the wrapped link, such as `is_linked`.
+impl<L, T: ?Sized> Deref for UnsizedLink<L, T> {
+    type Target = L;
+
+    #[inline]
+    fn deref(&self) -> &L {
+        &self.link
+    }
+}
+
+// The recorded pointer is only used by the adapter while the object is linked
+// into a collection, at which point it points to the object itself.
+unsafe impl<L: Send, T: ?Sized> Send for UnsizedLink<L, T> {}
+
+// Provide an implementation of Clone which simply initializes the new link as
+// unlinked. This allows structs containing a link to derive Clone.
+impl<L: Clone, T: ?Sized> Clone for UnsizedLink<L, T> {
+    #[inline]
+    fn clone(&self) -> UnsizedLink<L, T> {
+        UnsizedLink::new(self.link.clone())
+    }
+}
+
+// Same as above
+impl<L: Default, T: ?Sized> Default for UnsizedLink<L, T> {
+    #[inline]
+    fn default() -> UnsizedLink<L, T> {
+        UnsizedLink::new(L::default())
+    }
+}
+
+// Provide an implementation of Debug so that structs containing a link can
+// still derive Debug.
+impl<L: fmt::Debug, T: ?Sized> fmt::Debug for UnsizedLink<L, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.link.fmt(f)
+    }
+}
+
+/// Trait for unsized types which contain an `UnsizedLink`.
+///
+/// This is typically implemented for a trait object type by forwarding to a
+/// method of the trait, or for a struct ending with a slice by returning one
+/// of its fields.
+///
+/// # Safety
+///
+/// `unsized_link` must always return the same link, which must be stored
+/// inline in `self`. When implementing this for `dyn Trait` by calling a
+/// method of `Trait`, `Trait` should itself be an `unsafe trait` with the same
+/// requirement since any implementation of it can be used here.
+pub unsafe trait HasUnsizedLink<L> {
+    /// Returns the link within `self`.
+    fn unsized_link(&self) -> &UnsizedLink<L, Self>;
+}
+
+// =============================================================================
+// UnsizedAdapter
+// =============================================================================
+
+/// Adapter for collections of unsized values which contain an `UnsizedLink`.
+///
+/// `P` is the pointer type used to own the objects, for example
+/// `Box<dyn Trait>`, and `L` is the link type wrapped by the `UnsizedLink`.
+///
+/// # Examples
+///
+/// ```
+/// use intrusive_collections::{
+///     HasUnsizedLink, LinkedList, LinkedListLink, UnsizedAdapter, UnsizedLink,
+/// };
+///
+/// type TaskLink = UnsizedLink<LinkedListLink, dyn Task>;
+///
+/// // Implementations must return a link stored inline in `self`.
+/// unsafe trait Task {
+///     fn link(&self) -> &TaskLink;
+///     fn run(&self) -> u32;
+/// }
+///
+/// unsafe impl HasUnsizedLink<LinkedListLink> for dyn Task {
+///     fn unsized_link(&self) -> &TaskLink {
+///         self.link()
+///     }
+/// }
+///
+/// struct Add {
+///     link: TaskLink,
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// unsafe impl Task for Add {
+///     fn link(&self) -> &TaskLink {
+///         &self.link
+///     }
+///     fn run(&self) -> u32 {
+///         self.a + self.b
+///     }
+/// }
+///
+/// struct Constant {
+///     link: TaskLink,
+///     value: u32,
+/// }
+///
+/// unsafe impl Task for Constant {
+///     fn link(&self) -> &TaskLink {
+///         &self.link
+///     }
+///     fn run(&self) -> u32 {
+///         self.value
+///     }
+/// }
+///
+/// let mut tasks = LinkedList::new(UnsizedAdapter::<Box<dyn Task>, LinkedListLink>::new());
+/// tasks.push_back(Box::new(Add { link: TaskLink::default(), a: 1, b: 2 }));
+/// tasks.push_back(Box::new(Constant { link: TaskLink::default(), value: 4 }));
+/// assert_eq!(tasks.iter().map(|t| t.run()).sum::<u32>(), 7);
+/// ```
+pub struct UnsizedAdapter<P, L: DefaultLinkOps> {
+    link_ops: L::Ops,
+    pointer_ops: DefaultPointerOps<P>,
+}
+
+unsafe impl<P, L: DefaultLinkOps> Send for UnsizedAdapter<P, L> {}
+unsafe impl<P, L: DefaultLinkOps> Sync for UnsizedAdapter<P, L> {}
+
+impl<P, L: DefaultLinkOps> UnsizedAdapter<P, L> {
+    /// Constant instance of the adapter.
+    pub const NEW: Self = UnsizedAdapter {
+        link_ops: L::NEW,
+        pointer_ops: DefaultPointerOps::new(),
+    };
+
+    /// Creates a new `UnsizedAdapter`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::NEW
+    }
+}
+
+impl<P, L: DefaultLinkOps> Clone for UnsizedAdapter<P, L>
+where
+    L::Ops: Clone,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        UnsizedAdapter {
+            link_ops: self.link_ops.clone(),
+            pointer_ops: self.pointer_ops,
+        }
+    }
+}
+
+impl<P, L: DefaultLinkOps> Copy for UnsizedAdapter<P, L> where L::Ops: Copy {}
+
+impl<P, L: DefaultLinkOps> Default for UnsizedAdapter<P, L> {
+    #[inline]
+    fn default() -> Self {
+        Self::NEW
+    }
+}
+
+impl<P, L: DefaultLinkOps> fmt::Debug for UnsizedAdapter<P, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("UnsizedAdapter")
+    }
+}
+
+unsafe impl<P, L> Adapter for UnsizedAdapter<P, L>
+where
+    L: DefaultLinkOps,
+    L::Ops: LinkOps<LinkPtr = NonNull<L>>,
+    DefaultPointerOps<P>: PointerOps,
+    <DefaultPointerOps<P> as PointerOps>::Value: HasUnsizedLink<L>,
+{
+    type LinkOps = L::Ops;
+    type PointerOps = DefaultPointerOps<P>;
+
+    #[inline]
+    unsafe fn get_value(
+        &self,
+        link: <Self::LinkOps as LinkOps>::LinkPtr,
+    ) -> *const <Self::PointerOps as PointerOps>::Value {
+        // The wrapped link is the first field of the `#[repr(C)]` UnsizedLink,
+        // and the pointer was recorded by `get_link` when the object was
+        // inserted.
+        let link = link.cast::<UnsizedLink<L, _>>();
+        match link.as_ref().value.get() {
+            Some(value) => value.as_ptr(),
+            None => unreachable!("UnsizedLink used without UnsizedAdapter"),
+        }
+    }
+
+    #[inline]
+    unsafe fn get_link(
+        &self,
+        value: *const <Self::PointerOps as PointerOps>::Value,
+    ) -> <Self::LinkOps as LinkOps>::LinkPtr {
+        let link = (*value).unsized_link();
+        link.value
+            .set(Some(NonNull::new_unchecked(value as *mut _)));
+        NonNull::from(&link.link)
+    }
+
+    #[inline]
+    fn link_ops(&self) -> &Self::LinkOps {
+        &self.link_ops
+    }
+
+    #[inline]
+    fn link_ops_mut(&mut self) -> &mut Self::LinkOps {
+        &mut self.link_ops
+    }
+
+    #[inline]
+    fn pointer_ops(&self) -> &Self::PointerOps {
+        &self.pointer_ops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HasUnsizedLink, UnsizedAdapter, UnsizedLink};
+    use crate::{
+        KeyAdapter, LinkedList, LinkedListLink, RBTree, RBTreeLink, SinglyLinkedList,
+        SinglyLinkedListLink,
+    };
+    use std::boxed::Box;
+    use std::rc::Rc;
+    use std::vec;
+    use std::vec::Vec;
+
+    // Slice-tailed struct, created by unsizing a `Bytes<[u8; N]>`.
+    struct Bytes<D: ?Sized> {
+        link: UnsizedLink<SinglyLinkedListLink, Packet>,
+        tree_link: UnsizedLink<RBTreeLink, Packet>,
+        data: D,
+    }
+    type Packet = Bytes<[u8]>;
+
+    unsafe impl HasUnsizedLink<SinglyLinkedListLink> for Packet {
+        fn unsized_link(&self) -> &UnsizedLink<SinglyLinkedListLink, Packet> {
+            &self.link
+        }
+    }
+
+    unsafe impl HasUnsizedLink<RBTreeLink> for Packet {
+        fn unsized_link(&self) -> &UnsizedLink<RBTreeLink, Packet> {
+            &self.tree_link
+        }
+    }
+
+    impl<'a> KeyAdapter<'a> for UnsizedAdapter<Rc<Packet>, RBTreeLink> {
+        type Key = &'a [u8];
+        fn get_key(&self, value: &'a Packet) -> &'a [u8] {
+            &value.data
+        }
+    }
+
+    macro_rules! make_packet {
+        ($($byte:expr),*) => {
+            Rc::new(Bytes {
+                link: UnsizedLink::default(),
+                tree_link: UnsizedLink::default(),
+                data: [$($byte as u8),*],
+            }) as Rc<Packet>
+        };
+    }
+
+    #[test]
+    fn test_slice_tail() {
+        let mut a =
+            SinglyLinkedList::new(UnsizedAdapter::<Rc<Packet>, SinglyLinkedListLink>::new());
+        let mut b = RBTree::new(UnsizedAdapter::<Rc<Packet>, RBTreeLink>::new());
+        let packets = vec![make_packet!(3, 1), make_packet!(1, 2, 3), make_packet!(2)];
+        for p in packets {
+            a.push_front(p.clone());
+            b.insert(p);
+        }
+        let lens: Vec<_> = a.iter().map(|p| p.data.len()).collect();
+        assert_eq!(lens, [1, 3, 2]);
+        let data: Vec<_> = b.iter().map(|p| &p.data).collect();
+        assert_eq!(data, [&[1, 2, 3][..], &[2], &[3, 1]]);
+        assert!(b.find(&[2][..]).get().unwrap().link.is_linked());
+
+        let p = a.pop_front().unwrap();
+        assert!(!p.link.is_linked());
+        assert_eq!(&p.data, &[2]);
+    }
+
+    /// # Safety
+    ///
+    /// `link` must return a link stored inline in `self`.
+    unsafe trait Named {
+        fn link(&self) -> &UnsizedLink<LinkedListLink, dyn Named>;
+        fn name(&self) -> &str;
+    }
+
+    unsafe impl HasUnsizedLink<LinkedListLink> for dyn Named {
+        fn unsized_link(&self) -> &UnsizedLink<LinkedListLink, dyn Named> {
+            self.link()
+        }
+    }
+
+    struct Person(UnsizedLink<LinkedListLink, dyn Named>);
+    struct Pet(&'static str, UnsizedLink<LinkedListLink, dyn Named>);
+
+    unsafe impl Named for Person {
+        fn link(&self) -> &UnsizedLink<LinkedListLink, dyn Named> {
+            &self.0
+        }
+        fn name(&self) -> &str {
+            "person"
+        }
+    }
+
+    unsafe impl Named for Pet {
+        fn link(&self) -> &UnsizedLink<LinkedListLink, dyn Named> {
+            &self.1
+        }
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_trait_object() {
+        let mut a = LinkedList::new(UnsizedAdapter::<Box<dyn Named>, LinkedListLink>::new());
+        a.push_back(Box::new(Person(UnsizedLink::default())));
+        a.push_front(Box::new(Pet("cat", UnsizedLink::default())));
+        a.push_back(Box::new(Pet("dog", UnsizedLink::default())));
+        let names: Vec<_> = a.iter().map(|x| x.name()).collect();
+        assert_eq!(names, ["cat", "person", "dog"]);
+
+        let pet = a.pop_back().unwrap();
+        assert_eq!(pet.name(), "dog");
+        assert!(!pet.link().is_linked());
+        a.push_front(pet);
+        let names: Vec<_> = a.iter().rev().map(|x| x.name()).collect();
+        assert_eq!(names, ["person", "cat", "dog"]);
+    }
+}
@@ -13,11 +13,14 @@
 
 use core::cell::Cell;
 use core::fmt;
-use core::ptr::NonNull;
+use core::mem::{self, ManuallyDrop};
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
 
 use crate::link_ops::{self, DefaultLinkOps};
-use crate::pointer_ops::PointerOps;
-use crate::singly_linked_list::SinglyLinkedListOps;
+use crate::linked_list::{LinkedList, LinkedListOps};
+use crate::pointer_ops::{DataMut, PinnedPointerOps, PointerOps, UniquePointerOps};
+use crate::singly_linked_list::{SinglyLinkedList, SinglyLinkedListOps};
 use crate::unchecked_option::UncheckedOptionExt;
 use crate::Adapter;
 
@@ -133,6 +136,19 @@ impl DefaultLinkOps for Link {
 // An object containing a link can be sent to another thread if it is unlinked.
 unsafe impl Send for Link {}
 
+// Freeing an object which is still linked leaves a dangling pointer in the
+// collection, so turn this into a panic when debugging.
+#[cfg(feature = "debug-unsafe-ref")]
+impl Drop for Link {
+    #[inline]
+    fn drop(&mut self) {
+        assert!(
+            !self.is_linked(),
+            "`XorLinkedListLink` dropped while still linked into a `XorLinkedList`"
+        );
+    }
+}
+
 // Provide an implementation of Clone which simply initializes the new link as
 // unlinked. This allows structs containing a link to derive Clone.
 impl Clone for Link {
@@ -273,6 +289,21 @@ unsafe fn link_between<T: XorLinkedListOps>(
     link_ops.set(ptr, prev, next);
 }
 
+// Records that the links of the chain starting at `head` now belong to
+// `link_ops`, after they were moved in from another list.
+#[inline]
+unsafe fn adopt<T: XorLinkedListOps>(link_ops: &mut T, head: T::LinkPtr) {
+    if T::TRACKS_OWNER {
+        let mut prev = None;
+        let mut current = Some(head);
+        while let Some(x) = current {
+            link_ops.adopt_link(x);
+            current = link_ops.next(x, prev);
+            prev = Some(x);
+        }
+    }
+}
+
 // =============================================================================
 // Cursor, CursorMut
 // =============================================================================
@@ -403,6 +434,13 @@ where
         prev.move_prev();
         prev
     }
+
+    /// Converts the cursor into a `RevCursor` pointing to the same element,
+    /// which moves through the `XorLinkedList` in the opposite direction.
+    #[inline]
+    pub fn rev(self) -> RevCursor<'a, A> {
+        RevCursor { cursor: self }
+    }
 }
 
 /// A cursor which provides mutable access to a `XorLinkedList`.
@@ -569,6 +607,22 @@ where
         }
     }
 
+    /// Removes the current element from the `XorLinkedList` and passes it to
+    /// `dispose`.
+    ///
+    /// This behaves like `remove`, except that the removed element is handed
+    /// to `dispose` instead of being returned, and the cursor is moved to point
+    /// to the next element.
+    ///
+    /// Returns `true` if an element was removed.
+    #[inline]
+    pub fn remove_and_dispose<F>(&mut self, dispose: F) -> bool
+    where
+        F: FnOnce(<A::PointerOps as PointerOps>::Pointer),
+    {
+        self.remove().map(dispose).is_some()
+    }
+
     /// Removes the current element from the `XorLinkedList` and inserts another
     /// object in its place.
     ///
@@ -721,6 +775,7 @@ where
                 let tail = list.tail.unwrap_unchecked();
 
                 let link_ops = self.list.adapter.link_ops_mut();
+                adopt(link_ops, head);
 
                 if let Some(current) = self.current {
                     if let Some(next) = self.next {
@@ -759,6 +814,7 @@ where
                 let tail = list.tail.unwrap_unchecked();
 
                 let link_ops = self.list.adapter.link_ops_mut();
+                adopt(link_ops, head);
 
                 if let Some(current) = self.current {
                     if let Some(prev) = self.prev {
@@ -796,6 +852,8 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         if let Some(current) = self.current {
             unsafe {
                 let mut list = XorLinkedList {
@@ -817,6 +875,10 @@ where
                     .adapter
                     .link_ops_mut()
                     .set(current, self.prev, None);
+                list.adapter.link_ops_mut().renew_owner();
+                if let Some(head) = list.head {
+                    adopt(list.adapter.link_ops_mut(), head);
+                }
                 self.list.tail = self.current;
                 list
             }
@@ -828,6 +890,7 @@ where
             };
             self.list.head = None;
             self.list.tail = None;
+            self.list.adapter.link_ops_mut().renew_owner();
             list
         }
     }
@@ -843,6 +906,8 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         if let Some(current) = self.current {
             unsafe {
                 let mut list = XorLinkedList {
@@ -864,6 +929,10 @@ where
                     .adapter
                     .link_ops_mut()
                     .set(current, None, self.next);
+                list.adapter.link_ops_mut().renew_owner();
+                if let Some(head) = list.head {
+                    adopt(list.adapter.link_ops_mut(), head);
+                }
                 self.list.head = self.current;
                 list
             }
@@ -875,11 +944,156 @@ where
             };
             self.list.head = None;
             self.list.tail = None;
+            self.list.adapter.link_ops_mut().renew_owner();
             list
         }
     }
 }
 
+impl<'a, A: Adapter> Cursor<'a, A>
+where
+    A::LinkOps: XorLinkedListOps,
+    A::PointerOps: PinnedPointerOps,
+{
+    /// Returns a pinned reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This is only available for collections of pinned pointers such as
+    /// `Pin<Box<T>>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_pin(&self) -> Option<Pin<&'a <A::PointerOps as PointerOps>::Value>> {
+        self.get().map(|value| unsafe { Pin::new_unchecked(value) })
+    }
+}
+
+impl<'a, A: Adapter> CursorMut<'a, A>
+where
+    A::LinkOps: XorLinkedListOps,
+    A::PointerOps: PinnedPointerOps,
+{
+    /// Returns a pinned reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This is only available for collections of pinned pointers such as
+    /// `Pin<Box<T>>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_pin(&self) -> Option<Pin<&<A::PointerOps as PointerOps>::Value>> {
+        self.get().map(|value| unsafe { Pin::new_unchecked(value) })
+    }
+}
+
+impl<'a, A: Adapter> CursorMut<'a, A>
+where
+    A::LinkOps: XorLinkedListOps,
+    A::PointerOps: UniquePointerOps,
+    <A::PointerOps as PointerOps>::Value: DataMut,
+{
+    /// Returns a mutable reference to the data of the object that the cursor
+    /// is currently pointing to.
+    ///
+    /// This is only available for collections of uniquely owned pointers such
+    /// as `Box<T>`, and returns `None` if the cursor is pointing to the null
+    /// object.
+    #[inline]
+    pub fn get_data_mut(
+        &mut self,
+    ) -> Option<&mut <<A::PointerOps as PointerOps>::Value as DataMut>::Data> {
+        let value = unsafe { self.list.adapter.get_value(self.current?) };
+        Some(unsafe { (*(value as *mut <A::PointerOps as PointerOps>::Value)).data_mut() })
+    }
+}
+
+/// A cursor which provides read-only access to a `XorLinkedList` in reverse
+/// order.
+///
+/// This wraps a `Cursor` and swaps the meaning of "next" and "previous", so
+/// that code written in terms of `move_next` walks the list from back to
+/// front. It is created by `Cursor::rev`.
+pub struct RevCursor<'a, A: Adapter>
+where
+    A::LinkOps: XorLinkedListOps,
+{
+    cursor: Cursor<'a, A>,
+}
+
+impl<'a, A: Adapter> Clone for RevCursor<'a, A>
+where
+    A::LinkOps: XorLinkedListOps,
+{
+    #[inline]
+    fn clone(&self) -> RevCursor<'a, A> {
+        RevCursor {
+            cursor: self.cursor.clone(),
+        }
+    }
+}
+
+impl<'a, A: Adapter> RevCursor<'a, A>
+where
+    A::LinkOps: XorLinkedListOps,
+{
+    /// Checks if the cursor is currently pointing to the null object.
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        self.cursor.is_null()
+    }
+
+    /// Returns a reference to the object that the cursor is currently
+    /// pointing to.
+    ///
+    /// This returns `None` if the cursor is currently pointing to the null
+    /// object.
+    #[inline]
+    pub fn get(&self) -> Option<&'a <A::PointerOps as PointerOps>::Value> {
+        self.cursor.get()
+    }
+
+    /// Moves the cursor to the next element in reverse order, which is the
+    /// previous element of the `XorLinkedList`.
+    ///
+    /// If the cursor is pointer to the null object then this will move it to
+    /// the last element of the `XorLinkedList`.
+    #[inline]
+    pub fn move_next(&mut self) {
+        self.cursor.move_prev();
+    }
+
+    /// Moves the cursor to the previous element in reverse order, which is
+    /// the next element of the `XorLinkedList`.
+    ///
+    /// If the cursor is pointer to the null object then this will move it to
+    /// the first element of the `XorLinkedList`.
+    #[inline]
+    pub fn move_prev(&mut self) {
+        self.cursor.move_next();
+    }
+
+    /// Returns a cursor pointing to the next element in reverse order.
+    #[inline]
+    pub fn peek_next(&self) -> RevCursor<'_, A> {
+        let mut next = self.clone();
+        next.move_next();
+        next
+    }
+
+    /// Returns a cursor pointing to the previous element in reverse order.
+    #[inline]
+    pub fn peek_prev(&self) -> RevCursor<'_, A> {
+        let mut prev = self.clone();
+        prev.move_prev();
+        prev
+    }
+
+    /// Converts the cursor back into a `Cursor` which moves in the normal
+    /// direction.
+    #[inline]
+    pub fn rev(self) -> Cursor<'a, A> {
+        self.cursor
+    }
+}
+
 // =============================================================================
 // XorLinkedList
 // =============================================================================
@@ -926,6 +1140,36 @@ where
         }
     }
 
+    // Creates a `XorLinkedList` from the first and last elements of a list that
+    // was threaded through the links by another collection.
+    #[inline]
+    pub(crate) unsafe fn from_raw_parts(
+        head: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        tail: Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        adapter: A,
+    ) -> XorLinkedList<A> {
+        XorLinkedList {
+            head,
+            tail,
+            adapter,
+        }
+    }
+
+    // Takes the `XorLinkedList` apart without unlinking its elements, so that
+    // they can be re-threaded into another collection sharing the same links.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn into_raw_parts(
+        self,
+    ) -> (
+        Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        Option<<A::LinkOps as link_ops::LinkOps>::LinkPtr>,
+        A,
+    ) {
+        let list = ManuallyDrop::new(self);
+        (list.head, list.tail, unsafe { ptr::read(&list.adapter) })
+    }
+
     /// Creates an empty `XorLinkedList`.
     #[cfg(not(feature = "nightly"))]
     #[inline]
@@ -1143,19 +1387,39 @@ where
     /// converted back to an owned pointer and then dropped.
     #[inline]
     pub fn clear(&mut self) {
+        self.clear_and_dispose(drop);
+    }
+
+    /// Removes all elements from the `XorLinkedList`, passing each one to
+    /// `dispose`.
+    ///
+    /// This behaves like `clear`, except that each element is converted back
+    /// to an owned pointer and handed to `dispose` instead of being dropped.
+    /// This can be used to return elements to a pool or to run some custom
+    /// teardown on them.
+    #[inline]
+    pub fn clear_and_dispose<F>(&mut self, mut dispose: F)
+    where
+        F: FnMut(<A::PointerOps as PointerOps>::Pointer),
+    {
         use link_ops::LinkOps;
 
         let mut current = self.head;
         let mut prev = None;
         self.head = None;
         self.tail = None;
+        // Renew the owner first, so that elements which are left linked if
+        // `dispose` panics don't appear to belong to this collection.
+        self.adapter.link_ops_mut().renew_owner();
         while let Some(x) = current {
             unsafe {
                 let next = self.adapter.link_ops().next(x, prev);
                 self.adapter.link_ops_mut().release_link(x);
-                self.adapter
-                    .pointer_ops()
-                    .from_raw(self.adapter.get_value(x));
+                dispose(
+                    self.adapter
+                        .pointer_ops()
+                        .from_raw(self.adapter.get_value(x)),
+                );
                 prev = current;
                 current = next;
             }
@@ -1170,8 +1434,11 @@ where
     /// `force_unlink` function on them.
     #[inline]
     pub fn fast_clear(&mut self) {
+        use link_ops::LinkOps;
+
         self.head = None;
         self.tail = None;
+        self.adapter.link_ops_mut().renew_owner();
     }
 
     /// Takes all the elements out of the `XorLinkedList`, leaving it empty.
@@ -1181,6 +1448,8 @@ where
     where
         A: Clone,
     {
+        use link_ops::LinkOps;
+
         let list = XorLinkedList {
             head: self.head,
             tail: self.tail,
@@ -1188,6 +1457,7 @@ where
         };
         self.head = None;
         self.tail = None;
+        self.adapter.link_ops_mut().renew_owner();
         list
     }
 
@@ -1211,6 +1481,19 @@ where
         self.front_mut().remove()
     }
 
+    /// Removes the first element of the `XorLinkedList` and passes it to
+    /// `dispose`.
+    ///
+    /// Returns `true` if an element was removed, or `false` if the
+    /// `XorLinkedList` was empty.
+    #[inline]
+    pub fn pop_front_and_dispose<F>(&mut self, dispose: F) -> bool
+    where
+        F: FnOnce(<A::PointerOps as PointerOps>::Pointer),
+    {
+        self.pop_front().map(dispose).is_some()
+    }
+
     /// Removes the last element of the `XorLinkedList`.
     ///
     /// This returns `None` if the `XorLinkedList` is empty.
@@ -1218,6 +1501,68 @@ where
     pub fn pop_back(&mut self) -> Option<<A::PointerOps as PointerOps>::Pointer> {
         self.back_mut().remove()
     }
+
+    /// Reverses the order of the elements in the `XorLinkedList`.
+    ///
+    /// Since each link only stores the XOR of its neighbours, this simply
+    /// swaps the head and tail of the list and takes O(1) time.
+    #[inline]
+    pub fn reverse(&mut self) {
+        mem::swap(&mut self.head, &mut self.tail);
+    }
+}
+
+impl<A: Adapter> XorLinkedList<A>
+where
+    A::LinkOps: XorLinkedListOps + LinkedListOps,
+{
+    /// Converts the `XorLinkedList` into a `LinkedList` containing the same
+    /// elements in the same order.
+    ///
+    /// The elements are re-threaded through their existing links in O(n) time
+    /// without being unlinked or converted back into owned pointers, which
+    /// requires the link type to support both collections.
+    #[inline]
+    pub fn into_linked_list(self) -> LinkedList<A> {
+        let (head, tail, mut adapter) = self.into_raw_parts();
+        unsafe {
+            let mut prev = None;
+            let mut current = head;
+            while let Some(x) = current {
+                current = XorLinkedListOps::next(adapter.link_ops(), x, prev);
+                LinkedListOps::set_prev(adapter.link_ops_mut(), x, prev);
+                LinkedListOps::set_next(adapter.link_ops_mut(), x, current);
+                prev = Some(x);
+            }
+            LinkedList::from_raw_parts(head, tail, adapter)
+        }
+    }
+}
+
+impl<A: Adapter> XorLinkedList<A>
+where
+    A::LinkOps: XorLinkedListOps + SinglyLinkedListOps,
+{
+    /// Converts the `XorLinkedList` into a `SinglyLinkedList` containing the same
+    /// elements in the same order.
+    ///
+    /// The elements are re-threaded through their existing links in O(n) time
+    /// without being unlinked or converted back into owned pointers, which
+    /// requires the link type to support both collections.
+    #[inline]
+    pub fn into_singly_list(self) -> SinglyLinkedList<A> {
+        let (head, _, mut adapter) = self.into_raw_parts();
+        unsafe {
+            let mut prev = None;
+            let mut current = head;
+            while let Some(x) = current {
+                current = XorLinkedListOps::next(adapter.link_ops(), x, prev);
+                SinglyLinkedListOps::set_next(adapter.link_ops_mut(), x, current);
+                prev = Some(x);
+            }
+            SinglyLinkedList::from_raw_parts(head, adapter)
+        }
+    }
 }
 
 // Allow read-only access to values from multiple threads
@@ -1407,6 +1752,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::{Link, XorLinkedList};
+    use crate::SinglyLinkedList;
     use core::cell::Cell;
     use core::ptr;
     use std::boxed::Box;
@@ -1767,6 +2113,66 @@ mod tests {
         assert!(!d.link1.is_linked());
     }
 
+    #[test]
+    fn test_into_singly_list() {
+        let a = make_obj(1);
+        let mut l = XorLinkedList::new(ObjAdapter1::new());
+        l.push_back(a.clone());
+        l.push_back(make_obj(2));
+        l.push_front(make_obj(0));
+
+        let mut s: SinglyLinkedList<ObjAdapter1> = l.into_singly_list();
+        assert_eq!(s.iter().map(|x| x.value).collect::<Vec<_>>(), [0, 1, 2]);
+        assert!(a.link1.is_linked());
+        assert_eq!(s.pop_front().unwrap().value, 0);
+        s.clear();
+        assert!(!a.link1.is_linked());
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut l = XorLinkedList::new(ObjAdapter1::new());
+        l.reverse();
+        assert!(l.is_empty());
+        for v in 0..5 {
+            l.push_back(make_obj(v));
+        }
+        l.reverse();
+        assert_eq!(
+            l.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [4, 3, 2, 1, 0]
+        );
+        assert_eq!(
+            l.iter().rev().map(|x| x.value).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(l.pop_back().unwrap().value, 0);
+        l.push_front(make_obj(5));
+        l.reverse();
+        assert_eq!(
+            l.iter().map(|x| x.value).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5]
+        );
+
+        let mut c = l.cursor().rev();
+        assert!(c.is_null());
+        c.move_next();
+        assert_eq!(c.get().unwrap().value, 5);
+        assert_eq!(c.peek_next().get().unwrap().value, 4);
+        assert!(c.peek_prev().is_null());
+        c.move_next();
+        c.move_next();
+        assert_eq!(c.get().unwrap().value, 3);
+        c.move_prev();
+        assert_eq!(c.get().unwrap().value, 4);
+        let mut c = c.rev();
+        c.move_next();
+        assert_eq!(c.get().unwrap().value, 5);
+        let mut c = l.front().rev();
+        c.move_next();
+        assert!(c.is_null());
+    }
+
     #[test]
     fn test_multi_list() {
         let mut l1 = XorLinkedList::new(ObjAdapter1::new());
@@ -1813,6 +2219,47 @@ mod tests {
         assert!(!c.link1.is_linked());
     }
 
+    #[test]
+    fn test_owner() {
+        use crate::link_ops::OwnerLinkOps;
+        use crate::{Adapter, TaggedLink};
+        use std::panic::{self, AssertUnwindSafe};
+
+        struct Tagged {
+            link: TaggedLink<Link>,
+        }
+        intrusive_adapter!(TaggedAdapter = Rc<Tagged>: Tagged { link: TaggedLink<Link> });
+        let a = Rc::new(Tagged {
+            link: TaggedLink::new(Link::new()),
+        });
+        let owns = |l: &XorLinkedList<TaggedAdapter>| unsafe {
+            l.adapter.link_ops().is_owned(l.adapter.get_link(&*a))
+        };
+
+        let mut l = XorLinkedList::new(TaggedAdapter::new());
+        l.push_front(a.clone());
+        assert!(owns(&l));
+
+        // Empty lists split off from `l` don't share its owner tag.
+        let m = l.back_mut().split_after();
+        assert!(m.is_empty());
+        assert!(!owns(&m));
+        let n = l.front_mut().split_before();
+        assert!(n.is_empty());
+        assert!(!owns(&n));
+        assert!(owns(&l));
+
+        // Elements left behind by a panicking `dispose` don't belong to `l`.
+        l.push_front(Rc::new(Tagged {
+            link: TaggedLink::new(Link::new()),
+        }));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| l.clear_and_dispose(|_| panic!())));
+        assert!(result.is_err());
+        assert!(l.is_empty());
+        assert!(a.link.is_linked());
+        assert!(!owns(&l));
+    }
+
     #[test]
     fn test_non_static() {
         #[derive(Clone)]